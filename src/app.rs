@@ -1,6 +1,18 @@
 use std::path::PathBuf;
 
-use crate::{config::Config, states::StateValues};
+use iced::{Theme, widget::text_editor};
+
+use crate::{
+    DispatchError,
+    config::Config,
+    file_tree::TreeKey,
+    paths::Paths,
+    runner::RunEvent,
+    search::SearchMode,
+    states::{ErrorNotice, ProjectAction, ProjectSort, StateValues, TreeAction, WorkspaceId},
+    templates::ProjectTemplate,
+    themes,
+};
 
 pub enum View {
     Onboarding1,
@@ -8,6 +20,9 @@ pub enum View {
     NoProjectSelected,
     CreatingProject,
     SelectingExistingProject,
+    ProjectSettings,
+    ExportingProject,
+    ImportingProject,
 }
 
 #[derive(Clone, Debug)]
@@ -20,16 +35,128 @@ pub enum Message {
     NewProjectNameChanged(String),
     ConfirmNewProject,
     CancelNewProject,
-    // Project selection dropdown messages
+    NewProjectTemplateSelected(ProjectTemplate),
+    OpenCollidingProject,
+    // Project picker messages
     LoadExistingProjects,
     ExistingProjectsLoaded(Vec<String>),
     SelectExistingProject(String),
-    ToggleProjectDropdown, 
+    ProjectFilterChanged(String),
+    ProjectSortChanged(ProjectSort),
+    MoveProjectSelection(isize),
+    SubmitProjectPicker,
     ConfirmSelectedProject,
     CancelProjectSelection,
+    // Project lifecycle messages
+    StartProjectAction(ProjectAction),
+    ProjectActionNameChanged(String),
+    ConfirmProjectAction,
+    CancelProjectAction,
+    RequestDeleteProject,
+    ConfirmDeleteProject,
+    UndoDeleteProject,
+    ArchiveProject,
+    UnarchiveProject(String),
+    ToggleShowArchived,
+    FolderPathChanged(String),
+    OpenFolder,
+    UnlinkProject,
+    // Recent project messages
+    OpenRecentProject(String),
+    TogglePinnedRecentProject(String),
+    RemoveRecentProject(String),
+    // Project settings messages
+    OpenProjectSettings,
+    ProjectSettingsNameChanged(String),
+    ProjectSettingsDescriptionChanged(String),
+    ProjectSettingsTagsChanged(String),
+    ProjectSettingsTargetChanged(String),
+    ProjectSettingsFileTypesChanged(String),
+    ProjectSettingsIgnorePatternsChanged(String),
+    SaveProjectSettings,
+    SaveProjectAsTemplate(bool), // Replaces a template of the same name when true
+    CancelProjectSettings,
+    // Bundle export/import messages
+    OpenExportProject,
+    ExportDestinationChanged(String),
+    ExportIncludeHistoryToggled(bool),
+    ConfirmExportProject,
+    CloseExportProject,
+    OpenImportProject,
+    ImportSourceChanged(String),
+    ConfirmImportProject,
+    CancelImportProject,
     // Panel resize messages
-    ResizeHorizontal(f32),  // For horizontal split between left and right
-    ResizeVertical(f32),    // For vertical split between top and bottom
+    ResizeHorizontal(f32), // For horizontal split between left and right
+    ResizeVertical(f32),   // For vertical split between top and bottom
+    ToggleFileTree,
+    ToggleInspector,
+    ResetLayout,
+    PersistLayout(WorkspaceId, u64), // Debounced save, carries the layout revision it was scheduled for
+    // File tree messages
+    ToggleDirectory(String),
+    ExpandAllDirectories,
+    CollapseAllDirectories,
+    ShowAllFilesToggled(bool),
+    TreeKeyPressed(TreeKey),
+    FileSelected(PathBuf),
+    // Editor messages
+    EditorAction(text_editor::Action),
+    ReformatJson(bool), // Pretty-prints when true, minifies when false
+    SaveOpenedFile,
+    RevertOpenedFile,
+    FilesChanged(WorkspaceId, Vec<PathBuf>),
+    FileWatchFailed(WorkspaceId, DispatchError),
+    OpenTreeMenu(String),
+    CloseTreeMenu,
+    StartTreeAction(TreeAction),
+    TreeActionNameChanged(String),
+    ConfirmTreeAction,
+    CancelTreeAction,
+    TreePressed(String),
+    TreeDragOver(String),
+    TreeReleased(String), // Key of the entry released on, `""` for the tree's empty space
+    CancelTreeDrag,
+    FilesHovered,
+    FilesHoveredLeft,
+    FileDropped(PathBuf),
+    // File finder messages
+    OpenQuickOpen,
+    QuickOpenQueryChanged(String),
+    MoveQuickOpenSelection(isize),
+    SubmitQuickOpen,
+    QuickOpenFile(PathBuf),
+    CloseQuickOpen,
+    // Search messages
+    ShowSearchPanel(bool),
+    SearchQueryChanged(String),
+    SearchModeSelected(SearchMode),
+    SearchCaseToggled(bool),
+    RunSearch,
+    SearchResultSelected(PathBuf, usize),
+    SearchReplacementChanged(String),
+    PreviewReplace,
+    CancelReplace,
+    ConfirmReplace,
+    UndoReplace,
+    // Procedure run messages
+    RunProcedure,
+    SetProcedurePaused(bool),
+    CancelProcedure,
+    ProcedureEvent(WorkspaceId, RunEvent),
+    // Tab messages
+    SelectTab(WorkspaceId),
+    CycleTabs(isize),
+    CloseTab(WorkspaceId),
+    NewTab,
+    ShowWorkspace,
+    // Error banner messages
+    DismissError,
+    RetryAfterError,
+    ReloadConfig,
+    // Theme messages
+    ThemeSelected(Theme),
+    ReloadThemes,
 }
 
 pub struct Dispatcher {
@@ -39,15 +166,70 @@ pub struct Dispatcher {
     pub view: View,
 }
 
-impl Default for Dispatcher {
-    fn default() -> Self {
-        let cwd = std::env::current_dir().expect("Failed to get current directory");
+impl Dispatcher {
+    pub fn new(paths: Paths) -> Self {
+        let cwd = std::env::current_dir().unwrap_or_default();
 
-        Self {
-            conf: Config::default(),
+        let mut dispatcher = Self {
+            conf: Config {
+                paths: paths.clone(),
+                ..Config::default()
+            },
             states: StateValues::new(),
             cwd,
             view: View::NoProjectSelected,
+        };
+
+        match Config::load(paths) {
+            Ok(conf) => {
+                dispatcher.conf = conf;
+                dispatcher.prune_recent_projects();
+                dispatcher.load_themes();
+            }
+            Err(error) => {
+                // Skip onboarding so the broken file is not overwritten before the user can fix it
+                dispatcher.conf.first_time_use = false;
+                dispatcher.report_error(error, Some(Message::ReloadConfig));
+            }
+        }
+
+        dispatcher
+    }
+
+    /// Queues an error for the banner. `retry` is sent again if the user presses "Retry".
+    pub fn report_error(&mut self, error: DispatchError, retry: Option<Message>) {
+        self.states
+            .errors
+            .notices
+            .push(ErrorNotice { error, retry });
+    }
+
+    /// Removes recent projects that were deleted on disk, saving the config if it changed.
+    pub fn prune_recent_projects(&mut self) {
+        match self.conf.prune_recent_projects() {
+            Ok(true) => self.save_config(),
+            Ok(false) => {}
+            Err(error) => self.report_error(error, None),
+        }
+    }
+
+    /// Rescans custom themes and activates the one named in the config.
+    pub fn load_themes(&mut self) {
+        let (custom, errors) = themes::load_custom_themes(&self.conf.paths.config_dir);
+        for error in errors {
+            self.report_error(error, Some(Message::ReloadThemes));
+        }
+
+        self.states.themes.available = themes::available_themes(custom);
+        self.states.themes.active =
+            themes::find_theme(&self.states.themes.available, &self.conf.theme)
+                .unwrap_or(themes::DEFAULT_THEME);
+    }
+
+    /// Saves the config, reporting failures through the error banner.
+    pub fn save_config(&mut self) {
+        if let Err(error) = self.conf.save() {
+            self.report_error(error, None);
         }
     }
 }
@@ -0,0 +1,209 @@
+//! Single-file project bundles (`.tnetpkg`) for moving projects between machines.
+//!
+//! A bundle is a gzip-compressed tar archive laid out as:
+//!
+//! ```text
+//! bundle.toml     bundle metadata, see [`BundleInfo`]
+//! project/        project.toml and every file the project's file types cover
+//! history/        run history, only when exported with history
+//! ```
+
+use std::{fs::File, path::Path};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    DispatchError,
+    config::unix_now,
+    project::{self, HISTORY_DIR, MANIFEST_FILE, ProjectManifest},
+};
+
+/// Extension used for bundle files.
+pub const BUNDLE_EXTENSION: &str = "tnetpkg";
+
+/// Newest bundle layout this build can read and the one it writes.
+pub const BUNDLE_VERSION: u32 = 1;
+
+const BUNDLE_INFO_FILE: &str = "bundle.toml";
+const PROJECT_PREFIX: &str = "project";
+const HISTORY_PREFIX: &str = "history";
+
+/// Contents of `bundle.toml`.
+#[derive(Serialize, Deserialize)]
+pub struct BundleInfo {
+    pub bundle_version: u32,
+    /// Directory name of the exported project.
+    pub project: String,
+    /// Seconds since the Unix epoch.
+    pub exported: u64,
+    pub includes_history: bool,
+}
+
+/// Writes `project_dir` into a bundle at `destination`.
+pub fn export_project(
+    project_dir: &Path,
+    project_name: &str,
+    manifest: &ProjectManifest,
+    destination: &Path,
+    include_history: bool,
+) -> Result<(), DispatchError> {
+    let io_error = |e| DispatchError::io("writing the project bundle", e);
+
+    let file = File::create(destination).map_err(io_error)?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let info = BundleInfo {
+        bundle_version: BUNDLE_VERSION,
+        project: project_name.to_string(),
+        exported: unix_now(),
+        includes_history: include_history,
+    };
+    let info = toml::to_string(&info)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(info.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(unix_now());
+    header.set_cksum();
+    archive
+        .append_data(&mut header, BUNDLE_INFO_FILE, info.as_bytes())
+        .map_err(io_error)?;
+
+    archive
+        .append_path_with_name(
+            project_dir.join(MANIFEST_FILE),
+            Path::new(PROJECT_PREFIX).join(MANIFEST_FILE),
+        )
+        .map_err(io_error)?;
+    for file in project::project_files(project_dir, &manifest.file_types)? {
+        let relative = file.strip_prefix(project_dir).unwrap_or(&file);
+        archive
+            .append_path_with_name(&file, Path::new(PROJECT_PREFIX).join(relative))
+            .map_err(io_error)?;
+    }
+
+    let history = project_dir.join(HISTORY_DIR);
+    if include_history && history.is_dir() {
+        archive
+            .append_dir_all(HISTORY_PREFIX, &history)
+            .map_err(io_error)?;
+    }
+
+    archive
+        .into_inner()
+        .and_then(GzEncoder::finish)
+        .map_err(io_error)?;
+    Ok(())
+}
+
+/// Unpacks the bundle at `source` into `data_dir` and returns the new project's name.
+///
+/// If the bundled name collides with one of `taken_names` a numeric suffix is added.
+pub fn import_bundle(
+    source: &Path,
+    data_dir: &Path,
+    taken_names: &[String],
+) -> Result<String, DispatchError> {
+    let staging = data_dir.join(format!(".import-{}", unix_now()));
+    let result = unpack_and_install(source, &staging, data_dir, taken_names);
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+fn unpack_and_install(
+    source: &Path,
+    staging: &Path,
+    data_dir: &Path,
+    taken_names: &[String],
+) -> Result<String, DispatchError> {
+    let file =
+        File::open(source).map_err(|e| DispatchError::io("opening the project bundle", e))?;
+    // `unpack` refuses entries that would escape `staging` (absolute paths, `..`)
+    tar::Archive::new(GzDecoder::new(file))
+        .unpack(staging)
+        .map_err(|e| DispatchError::io("unpacking the project bundle", e))?;
+
+    let info = std::fs::read_to_string(staging.join(BUNDLE_INFO_FILE)).map_err(|_| {
+        DispatchError::Project(format!("{} is not a project bundle", source.display()))
+    })?;
+    let info: BundleInfo = toml::from_str(&info)?;
+    if info.bundle_version > BUNDLE_VERSION {
+        return Err(DispatchError::Project(format!(
+            "bundle version {} is newer than this build supports ({BUNDLE_VERSION})",
+            info.bundle_version
+        )));
+    }
+
+    // Rejects manifests from newer builds before anything lands in the data directory
+    let unpacked_project = staging.join(PROJECT_PREFIX);
+    let mut manifest = ProjectManifest::load(&unpacked_project)?;
+
+    let name = free_project_name(&info.project, taken_names)?;
+    if name != info.project && manifest.name == info.project {
+        manifest.name = name.clone();
+    }
+    manifest.touch();
+    manifest.save(&unpacked_project)?;
+
+    let unpacked_history = staging.join(HISTORY_PREFIX);
+    if unpacked_history.is_dir() {
+        std::fs::rename(&unpacked_history, unpacked_project.join(HISTORY_DIR))
+            .map_err(|e| DispatchError::io("installing the run history", e))?;
+    }
+
+    let target = project::vacant_project_path(data_dir, &name)?;
+    std::fs::rename(&unpacked_project, target)
+        .map_err(|e| DispatchError::io("installing the imported project", e))?;
+    Ok(name)
+}
+
+/// Returns `name`, or `name-2`, `name-3`, ... whichever is not taken yet.
+fn free_project_name(name: &str, taken_names: &[String]) -> Result<String, DispatchError> {
+    project::validate_project_name(name, &[])
+        .map_err(|e| DispatchError::Project(format!("bundle has an invalid project name: {e}")))?;
+
+    let mut candidate = name.to_string();
+    let mut suffix = 2;
+    while project::find_colliding_project(&candidate, taken_names).is_some() {
+        candidate = format!("{name}-{suffix}");
+        suffix += 1;
+    }
+    Ok(candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_then_import_round_trips() {
+        let root =
+            std::env::temp_dir().join(format!("tnet-dispatch-bundle-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let data_dir = root.join("DATA");
+        let project_dir = data_dir.join("suite");
+        std::fs::create_dir_all(project_dir.join("packets")).unwrap();
+        std::fs::create_dir_all(project_dir.join(HISTORY_DIR)).unwrap();
+        std::fs::write(project_dir.join("packets/login.json"), "{}").unwrap();
+        std::fs::write(project_dir.join("notes.txt"), "skipped").unwrap();
+        std::fs::write(project_dir.join(HISTORY_DIR).join("run.toml"), "ok = true").unwrap();
+        let manifest = ProjectManifest::new("suite");
+        manifest.save(&project_dir).unwrap();
+
+        let bundle = root.join("suite.tnetpkg");
+        export_project(&project_dir, "suite", &manifest, &bundle, true).unwrap();
+
+        let imported = import_bundle(&bundle, &data_dir, &["suite".to_string()]).unwrap();
+        assert_eq!(imported, "suite-2");
+        let imported_dir = data_dir.join("suite-2");
+        assert!(imported_dir.join("packets/login.json").is_file());
+        assert!(!imported_dir.join("notes.txt").exists());
+        assert!(imported_dir.join(HISTORY_DIR).join("run.toml").is_file());
+        assert_eq!(
+            ProjectManifest::load(&imported_dir).unwrap().name,
+            "suite-2"
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
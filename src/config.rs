@@ -1,80 +1,389 @@
-use std::{fs::File, io::Read, path::PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    DispatchError,
+    paths::Paths,
+    project::{self, ARCHIVE_DIR},
+    themes::DEFAULT_THEME,
+};
+
+pub mod migrations;
+
 #[derive(Serialize, Deserialize)]
 pub struct Config {
+    /// Schema version, see [`migrations`].
+    pub version: u32,
     pub first_time_use: bool,
+    pub recent_projects: Vec<RecentProject>,
+    /// Display name of the selected theme, built-in or custom.
+    pub theme: String,
+    /// Projects opened in place from folders outside the data directory.
+    pub linked_projects: Vec<LinkedProject>,
+    /// Keys this build does not know about, kept so saving does not drop them.
+    #[serde(flatten)]
+    pub extra: toml::Table,
+    /// Where this config was loaded from; resolved at runtime, never written to disk.
+    #[serde(skip)]
+    pub paths: Paths,
+}
+
+/// A project the user has opened, remembered for the welcome screen.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecentProject {
+    pub name: String,
+    /// Seconds since the Unix epoch.
+    pub last_opened: u64,
+    pub pinned: bool,
+}
+
+/// A project folder outside the data directory, listed alongside the data projects.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LinkedProject {
+    /// Name the project is listed under, unique among all projects.
+    pub name: String,
+    pub path: PathBuf,
 }
 
 impl Config {
-    pub fn save(&self) -> Result<(), toml::ser::Error>{
-        let tnet_dispatch: PathBuf = PathBuf::from(env!("HOME")).join(".tnet").join("dispatch");
-        std::fs::create_dir_all(&tnet_dispatch).expect("Failed to create all directories.");
-        let config_path = tnet_dispatch.join("config.toml");
+    /// Loads the config file, writing a default one if it does not exist yet.
+    pub fn load(paths: Paths) -> Result<Self, DispatchError> {
+        std::fs::create_dir_all(&paths.config_dir)
+            .map_err(|e| DispatchError::io("creating the config directory", e))?;
+        let config_path = paths.config_file();
+        if !config_path.exists() {
+            let default_config = Config {
+                paths,
+                ..Config::default()
+            };
+            default_config.save()?;
+            return Ok(default_config);
+        }
+        let buffer = std::fs::read_to_string(&config_path)
+            .map_err(|e| DispatchError::io("reading the config file", e))?;
+        let mut table: toml::Table = toml::from_str(&buffer)?;
+        let migrated_from = migrations::migrate(&mut table)?;
+
+        let mut config: Config = toml::Value::Table(table).try_into()?;
+        config.paths = paths;
+
+        if let Some(old_version) = migrated_from {
+            let backup_path = config_path.with_extension(format!("toml.v{old_version}.bak"));
+            std::fs::copy(&config_path, &backup_path)
+                .map_err(|e| DispatchError::io("backing up the config file", e))?;
+            config.save()?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn save(&self) -> Result<(), DispatchError> {
+        std::fs::create_dir_all(&self.paths.config_dir)
+            .map_err(|e| DispatchError::io("creating the config directory", e))?;
         let toml_string = toml::to_string(self)?;
-        std::fs::write(&config_path, toml_string).expect("Failed to write to file.");
+        std::fs::write(self.paths.config_file(), toml_string)
+            .map_err(|e| DispatchError::io("writing the config file", e))?;
         Ok(())
     }
-    
-    pub fn get_data_path(&self) -> PathBuf {
-        let tnet_dispatch = PathBuf::from(env!("HOME")).join(".tnet").join("dispatch");
-        let data = tnet_dispatch.join("DATA");
-        std::fs::create_dir_all(&data).expect("Failed to create all directories.");
-        data
-    }
-    
-    pub fn get_available_projects(&self) -> Vec<String> {
-        let data_path = self.get_data_path();
-        
-        // Get all entries in the data directory
-        let entries = match std::fs::read_dir(&data_path) {
-            Ok(entries) => entries,
-            Err(e) => {
-                eprintln!("Failed to read data directory: {}", e);
-                return Vec::new();
+
+    pub fn get_data_path(&self) -> Result<PathBuf, DispatchError> {
+        let data = self.paths.data_dir.clone();
+        std::fs::create_dir_all(&data)
+            .map_err(|e| DispatchError::io("creating the data directory", e))?;
+        Ok(data)
+    }
+
+    /// Names of the projects in the data directory followed by the linked ones.
+    pub fn get_available_projects(&self) -> Result<Vec<String>, DispatchError> {
+        let data_path = self.get_data_path()?;
+        let mut projects = list_directories(&data_path)?;
+        projects.extend(
+            self.linked_projects
+                .iter()
+                .map(|linked| linked.name.clone()),
+        );
+        Ok(projects)
+    }
+
+    /// Where the project called `name` lives, following links to outside folders.
+    pub fn project_path(&self, name: &str) -> Result<PathBuf, DispatchError> {
+        match self.linked_project(name) {
+            Some(linked) => Ok(linked.path.clone()),
+            None => Ok(self.get_data_path()?.join(name)),
+        }
+    }
+
+    /// The data directory, for lifecycle operations that move `name` around inside it.
+    ///
+    /// Linked folders belong to the user and live elsewhere, so they are refused.
+    pub fn data_path_for_move(&self, name: &str) -> Result<PathBuf, DispatchError> {
+        if self.linked_project(name).is_some() {
+            return Err(DispatchError::Project(format!(
+                "'{name}' is a linked folder; only the link can be removed"
+            )));
+        }
+        self.get_data_path()
+    }
+
+    pub fn linked_project(&self, name: &str) -> Option<&LinkedProject> {
+        self.linked_projects
+            .iter()
+            .find(|linked| linked.name == name)
+    }
+
+    /// Registers `folder` as a linked project and returns the name it is listed under.
+    ///
+    /// The folder's own name is used, with a numeric suffix if another project already has it.
+    /// Linking a folder a second time returns the existing name.
+    pub fn link_project(&mut self, folder: &Path) -> Result<String, DispatchError> {
+        let path = folder
+            .canonicalize()
+            .map_err(|e| DispatchError::io(format!("opening {}", folder.display()), e))?;
+        if !path.is_dir() {
+            return Err(DispatchError::Project(format!(
+                "{} is not a folder",
+                path.display()
+            )));
+        }
+        if let Some(linked) = self
+            .linked_projects
+            .iter()
+            .find(|linked| linked.path == path)
+        {
+            return Ok(linked.name.clone());
+        }
+
+        let base = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| {
+                DispatchError::Project(format!("{} cannot be linked", path.display()))
+            })?;
+        let taken = self.get_taken_project_names()?;
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while project::find_colliding_project(&name, &taken).is_some() {
+            name = format!("{base}-{suffix}");
+            suffix += 1;
+        }
+
+        self.linked_projects.push(LinkedProject {
+            name: name.clone(),
+            path,
+        });
+        Ok(name)
+    }
+
+    /// Removes the link to `name`; the folder itself is left alone.
+    pub fn unlink_project(&mut self, name: &str) {
+        self.linked_projects.retain(|linked| linked.name != name);
+    }
+
+    pub fn get_archived_projects(&self) -> Result<Vec<String>, DispatchError> {
+        let archive_path = self.get_data_path()?.join(ARCHIVE_DIR);
+        if !archive_path.is_dir() {
+            return Ok(Vec::new());
+        }
+        list_directories(&archive_path)
+    }
+
+    /// Names of all active and archived projects, which new names must not collide with.
+    pub fn get_taken_project_names(&self) -> Result<Vec<String>, DispatchError> {
+        let mut names = self.get_available_projects()?;
+        names.extend(self.get_archived_projects()?);
+        Ok(names)
+    }
+
+    /// Points recent-project entries for `old_name` at `new_name` after a rename.
+    pub fn rename_recent_project(&mut self, old_name: &str, new_name: &str) {
+        for recent in &mut self.recent_projects {
+            if recent.name == old_name {
+                recent.name = new_name.to_string();
             }
-        };
-        
-        // Filter for directories and convert to project names
-        let mut projects = Vec::new();
-        for entry in entries {
-            if let Ok(entry) = entry {
-                if let Ok(file_type) = entry.file_type() {
-                    if file_type.is_dir() {
-                        if let Some(name) = entry.file_name().to_str() {
-                            projects.push(name.to_string());
-                        }
-                    }
-                }
+        }
+    }
+
+    /// Marks `name` as opened just now, adding it to the recent list if needed.
+    pub fn record_recent_project(&mut self, name: &str) {
+        let now = unix_now();
+        match self.recent_projects.iter_mut().find(|p| p.name == name) {
+            Some(recent) => recent.last_opened = now,
+            None => self.recent_projects.push(RecentProject {
+                name: name.to_string(),
+                last_opened: now,
+                pinned: false,
+            }),
+        }
+    }
+
+    /// Recent projects with pinned ones first, then most recently opened first.
+    pub fn sorted_recent_projects(&self) -> Vec<&RecentProject> {
+        let mut recent: Vec<_> = self.recent_projects.iter().collect();
+        recent.sort_by(|a, b| {
+            b.pinned
+                .cmp(&a.pinned)
+                .then(b.last_opened.cmp(&a.last_opened))
+        });
+        recent
+    }
+
+    /// Drops recent entries whose project directory no longer exists.
+    /// Returns whether anything was removed.
+    pub fn prune_recent_projects(&mut self) -> Result<bool, DispatchError> {
+        let before = self.recent_projects.len();
+        let mut kept = Vec::with_capacity(before);
+        for recent in std::mem::take(&mut self.recent_projects) {
+            if self.project_path(&recent.name)?.is_dir() {
+                kept.push(recent);
             }
         }
-        
-        projects
+        self.recent_projects = kept;
+        Ok(self.recent_projects.len() != before)
     }
 }
 
 impl Default for Config {
     fn default() -> Self {
-        let tnet_dispatch: PathBuf = PathBuf::from(env!("HOME")).join(".tnet").join("dispatch");
-        std::fs::create_dir_all(&tnet_dispatch).unwrap_or_else(|e| {
-            eprintln!("Failed to create directory structure: {}", e);
-        });
-        let config_path = tnet_dispatch.join("config.toml");
-        if !config_path.exists() {
-            let default_config = Config {
-                first_time_use: true,
-            };
-            let toml_string =
-                toml::to_string(&default_config).expect("Failed to serialize default config");
-            std::fs::write(&config_path, toml_string).expect("Failed to write default config file");
-            return default_config;
+        Self {
+            version: migrations::CURRENT_VERSION,
+            first_time_use: true,
+            recent_projects: Vec::new(),
+            theme: DEFAULT_THEME.to_string(),
+            linked_projects: Vec::new(),
+            extra: toml::Table::new(),
+            paths: Paths::default(),
         }
-        let mut contents = File::open(&config_path).expect("Failed to open config file");
-        let mut buffer = String::new();
-        contents
-            .read_to_string(&mut buffer)
-            .expect("Failed to read config file");
-        toml::from_str(buffer.as_str()).expect("Failed to parse config file")
+    }
+}
+
+/// Current time as seconds since the Unix epoch.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Returns the names of all directories directly inside `path`, skipping hidden ones
+/// such as the trash and archive folders.
+pub fn list_directories(path: &Path) -> Result<Vec<String>, DispatchError> {
+    let entries =
+        std::fs::read_dir(path).map_err(|e| DispatchError::io("reading the data directory", e))?;
+
+    // Filter for directories and convert to project names
+    let mut projects = Vec::new();
+    for entry in entries.flatten() {
+        if entry.file_type().is_ok_and(|file_type| file_type.is_dir())
+            && let Some(name) = entry.file_name().to_str()
+            && !name.starts_with('.')
+        {
+            projects.push(name.to_string());
+        }
+    }
+
+    Ok(projects)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_paths(name: &str) -> Paths {
+        let root = std::env::temp_dir().join(format!(
+            "tnet-dispatch-config-{}-{name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+        Paths::rooted_at(root)
+    }
+
+    #[test]
+    fn load_migrates_and_backs_up_old_file() {
+        let paths = temp_paths("migrate");
+        std::fs::create_dir_all(&paths.config_dir).unwrap();
+        let original = "first_time_use = false\nfuture_key = 7\n";
+        std::fs::write(paths.config_file(), original).unwrap();
+
+        let config = Config::load(paths.clone()).unwrap();
+        assert_eq!(config.version, migrations::CURRENT_VERSION);
+        assert!(!config.first_time_use);
+
+        let backup = paths.config_dir.join("config.toml.v0.bak");
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), original);
+
+        let rewritten: toml::Table =
+            toml::from_str(&std::fs::read_to_string(paths.config_file()).unwrap()).unwrap();
+        assert_eq!(rewritten["future_key"], toml::Value::Integer(7));
+
+        std::fs::remove_dir_all(&paths.config_dir).unwrap();
+    }
+
+    #[test]
+    fn prune_drops_missing_projects() {
+        let paths = temp_paths("prune");
+        let mut config = Config {
+            paths: paths.clone(),
+            ..Config::default()
+        };
+        std::fs::create_dir_all(paths.data_dir.join("kept")).unwrap();
+        config.record_recent_project("kept");
+        config.record_recent_project("gone");
+
+        assert!(config.prune_recent_projects().unwrap());
+        let names: Vec<_> = config
+            .recent_projects
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(names, ["kept"]);
+
+        std::fs::remove_dir_all(&paths.config_dir).unwrap();
+    }
+
+    #[test]
+    fn linked_folders_get_unique_names() {
+        let paths = temp_paths("link");
+        let mut config = Config {
+            paths: paths.clone(),
+            ..Config::default()
+        };
+        std::fs::create_dir_all(paths.data_dir.join("service")).unwrap();
+        let outside = paths.config_dir.join("repo/service");
+        std::fs::create_dir_all(&outside).unwrap();
+
+        let name = config.link_project(&outside).unwrap();
+        assert_eq!(name, "service-2");
+        assert_eq!(config.link_project(&outside).unwrap(), name);
+        assert_eq!(
+            config.project_path(&name).unwrap(),
+            outside.canonicalize().unwrap()
+        );
+        assert!(config.get_available_projects().unwrap().contains(&name));
+        assert!(config.data_path_for_move(&name).is_err());
+        assert_eq!(
+            config.data_path_for_move("service").unwrap(),
+            paths.data_dir
+        );
+
+        config.unlink_project(&name);
+        assert!(config.linked_project(&name).is_none());
+        assert!(outside.is_dir());
+
+        std::fs::remove_dir_all(&paths.config_dir).unwrap();
+    }
+
+    #[test]
+    fn load_creates_current_default() {
+        let paths = temp_paths("default");
+        let config = Config::load(paths.clone()).unwrap();
+        assert_eq!(config.version, migrations::CURRENT_VERSION);
+        assert!(config.first_time_use);
+        assert!(paths.config_file().exists());
+
+        std::fs::remove_dir_all(&paths.config_dir).unwrap();
     }
 }
@@ -0,0 +1,195 @@
+//! Step-by-step upgrades for `config.toml`.
+//!
+//! Each migration works on the raw TOML table rather than on [`Config`](super::Config), so
+//! keys this build does not know about pass through untouched. To change the schema, append a
+//! function to [`MIGRATIONS`]; [`CURRENT_VERSION`] follows automatically.
+
+use toml::{Table, Value};
+
+use crate::{DispatchError, themes::DEFAULT_THEME};
+
+/// Key holding the schema version. Files written before versioning have no such key (v0).
+pub const VERSION_KEY: &str = "version";
+
+type Migration = fn(&mut Table) -> Result<(), DispatchError>;
+
+/// `MIGRATIONS[n]` upgrades a version `n` table to version `n + 1`.
+const MIGRATIONS: &[Migration] = &[v0_to_v1, v1_to_v2, v2_to_v3, v3_to_v4];
+
+pub const CURRENT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Returns the schema version recorded in `table`.
+pub fn version_of(table: &Table) -> Result<u32, DispatchError> {
+    match table.get(VERSION_KEY) {
+        None => Ok(0),
+        Some(Value::Integer(version)) => u32::try_from(*version)
+            .map_err(|_| DispatchError::Config(format!("invalid config version {version}"))),
+        Some(other) => Err(DispatchError::Config(format!(
+            "config version must be an integer, found {other}"
+        ))),
+    }
+}
+
+/// Upgrades `table` to [`CURRENT_VERSION`] one step at a time.
+///
+/// Returns the version the table started at if anything was changed, so the caller can back up
+/// the original file before writing the upgraded one.
+pub fn migrate(table: &mut Table) -> Result<Option<u32>, DispatchError> {
+    let original = version_of(table)?;
+    if original > CURRENT_VERSION {
+        return Err(DispatchError::Config(format!(
+            "config.toml is version {original}, but this build only understands up to version {CURRENT_VERSION}"
+        )));
+    }
+    if original == CURRENT_VERSION {
+        return Ok(None);
+    }
+
+    for (version, migration) in MIGRATIONS.iter().enumerate().skip(original as usize) {
+        migration(table)?;
+        table.insert(VERSION_KEY.to_string(), Value::Integer(version as i64 + 1));
+    }
+
+    Ok(Some(original))
+}
+
+/// v1 introduces the `version` key itself. Unversioned files only ever held `first_time_use`.
+fn v0_to_v1(table: &mut Table) -> Result<(), DispatchError> {
+    table
+        .entry("first_time_use")
+        .or_insert(Value::Boolean(true));
+    Ok(())
+}
+
+/// v2 adds the recent-projects list shown on the welcome screen.
+fn v1_to_v2(table: &mut Table) -> Result<(), DispatchError> {
+    table
+        .entry("recent_projects")
+        .or_insert(Value::Array(Vec::new()));
+    Ok(())
+}
+
+/// v3 records the selected theme by name.
+fn v2_to_v3(table: &mut Table) -> Result<(), DispatchError> {
+    table
+        .entry("theme")
+        .or_insert(Value::String(DEFAULT_THEME.to_string()));
+    Ok(())
+}
+
+/// v4 adds projects linked in from folders outside the data directory.
+fn v3_to_v4(table: &mut Table) -> Result<(), DispatchError> {
+    table
+        .entry("linked_projects")
+        .or_insert(Value::Array(Vec::new()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Table {
+        toml::from_str(source).expect("test TOML should parse")
+    }
+
+    #[test]
+    fn unversioned_file_is_version_zero() {
+        assert_eq!(version_of(&parse("first_time_use = false")).unwrap(), 0);
+    }
+
+    #[test]
+    fn v0_upgrades_to_current_and_keeps_values() {
+        let mut table = parse("first_time_use = false");
+        assert_eq!(migrate(&mut table).unwrap(), Some(0));
+        assert_eq!(
+            table.get(VERSION_KEY),
+            Some(&Value::Integer(CURRENT_VERSION as i64))
+        );
+        assert_eq!(table.get("first_time_use"), Some(&Value::Boolean(false)));
+    }
+
+    #[test]
+    fn v0_to_v1_fills_missing_first_time_use() {
+        let mut table = Table::new();
+        migrate(&mut table).unwrap();
+        assert_eq!(table.get("first_time_use"), Some(&Value::Boolean(true)));
+    }
+
+    #[test]
+    fn v1_to_v2_adds_empty_recent_projects() {
+        let mut table = parse("version = 1\nfirst_time_use = false");
+        assert_eq!(migrate(&mut table).unwrap(), Some(1));
+        assert_eq!(
+            table.get(VERSION_KEY),
+            Some(&Value::Integer(CURRENT_VERSION as i64))
+        );
+        assert_eq!(
+            table.get("recent_projects"),
+            Some(&Value::Array(Vec::new()))
+        );
+    }
+
+    #[test]
+    fn v1_to_v2_keeps_existing_recent_projects() {
+        let mut table = parse(
+            "version = 1\nfirst_time_use = false\n[[recent_projects]]\nname = \"a\"\nlast_opened = 1\npinned = false",
+        );
+        migrate(&mut table).unwrap();
+        assert_eq!(table["recent_projects"].as_array().map(Vec::len), Some(1));
+    }
+
+    #[test]
+    fn v2_to_v3_adds_default_theme() {
+        let mut table = parse("version = 2\nfirst_time_use = false\nrecent_projects = []");
+        assert_eq!(migrate(&mut table).unwrap(), Some(2));
+        assert_eq!(
+            table.get("theme"),
+            Some(&Value::String(DEFAULT_THEME.to_string()))
+        );
+    }
+
+    #[test]
+    fn v3_to_v4_adds_empty_linked_projects() {
+        let mut table = parse("version = 3\nfirst_time_use = false\nrecent_projects = []");
+        assert_eq!(migrate(&mut table).unwrap(), Some(3));
+        assert_eq!(
+            table.get("linked_projects"),
+            Some(&Value::Array(Vec::new()))
+        );
+    }
+
+    #[test]
+    fn current_version_is_left_alone() {
+        let mut table = parse(&format!(
+            "version = {CURRENT_VERSION}\nfirst_time_use = true"
+        ));
+        let before = table.clone();
+        assert_eq!(migrate(&mut table).unwrap(), None);
+        assert_eq!(table, before);
+    }
+
+    #[test]
+    fn unknown_fields_survive_migration() {
+        let mut table =
+            parse("first_time_use = false\nfuture_key = \"kept\"\n[plugin]\nenabled = true");
+        migrate(&mut table).unwrap();
+        assert_eq!(
+            table.get("future_key"),
+            Some(&Value::String("kept".to_string()))
+        );
+        assert_eq!(table["plugin"]["enabled"], Value::Boolean(true));
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let mut table = parse(&format!("version = {}", CURRENT_VERSION + 1));
+        assert!(migrate(&mut table).is_err());
+    }
+
+    #[test]
+    fn malformed_version_is_rejected() {
+        assert!(version_of(&parse("version = \"one\"")).is_err());
+        assert!(version_of(&parse("version = -1")).is_err());
+    }
+}
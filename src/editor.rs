@@ -0,0 +1,246 @@
+//! The editor for the opened file: its buffer, unsaved changes and validation of packets and
+//! procedures.
+
+use std::path::Path;
+
+use iced::widget::text_editor::{Action, Content, Motion};
+
+use crate::{highlight, procedure};
+
+pub struct FileEditor {
+    pub content: Content,
+    /// Text as last read from or written to disk, normalized like [`Content::text`].
+    saved: String,
+    /// Set when the buffer differs from `saved`.
+    pub dirty: bool,
+    /// How the buffer is highlighted and validated.
+    pub language: Language,
+    /// Where the buffer stops being a valid packet or procedure.
+    pub error: Option<SourceError>,
+    /// `"\r\n"` or `"\n"`, whichever the file used when read; [`Content`] only keeps `\n`.
+    line_ending: &'static str,
+    /// Whether the file ended with a line ending; [`Content::text`] always adds one.
+    trailing_newline: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    Plain,
+    /// Packets, by their `.json` extension.
+    Json,
+    /// `.proc` files, see [`procedure`].
+    Procedure,
+}
+
+impl Language {
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::Json,
+            Some("proc") => Self::Procedure,
+            _ => Self::Plain,
+        }
+    }
+
+    /// The first error in `text`, if it does not parse.
+    fn validate(self, text: &str) -> Option<SourceError> {
+        match self {
+            Self::Plain => None,
+            Self::Json => {
+                let error = serde_json::from_str::<serde_json::Value>(text).err()?;
+                let line = error.line().saturating_sub(1);
+                // serde_json counts bytes, the highlighter and cursor count chars
+                let byte = error.column().saturating_sub(1);
+                let column = text.lines().nth(line).map_or(0, |line| {
+                    line.char_indices()
+                        .take_while(|(index, _)| *index < byte)
+                        .count()
+                });
+                Some(SourceError {
+                    line,
+                    column,
+                    message: error.to_string(),
+                })
+            }
+            Self::Procedure => {
+                let error = procedure::parse(text).err()?;
+                Some(SourceError {
+                    line: error.line - 1,
+                    column: error.column - 1,
+                    message: error.to_string(),
+                })
+            }
+        }
+    }
+}
+
+/// A parse error in the buffer; zero-based, with the column counted in chars.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl FileEditor {
+    pub fn new(contents: &str, language: Language) -> Self {
+        let content = Content::with_text(contents);
+        let saved = content.text();
+        let error = language.validate(&saved);
+        Self {
+            content,
+            saved,
+            dirty: false,
+            language,
+            error,
+            line_ending: line_ending(contents),
+            trailing_newline: contents.ends_with('\n'),
+        }
+    }
+
+    pub fn perform(&mut self, action: Action) {
+        let is_edit = action.is_edit();
+        self.content.perform(action);
+        if is_edit {
+            self.changed();
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.content.text()
+    }
+
+    /// `text` as it should be written to disk, with the line endings and final newline the file
+    /// was read with.
+    pub fn file_contents(&self, text: &str) -> String {
+        let text = if self.trailing_newline {
+            text
+        } else {
+            text.strip_suffix('\n').unwrap_or(text)
+        };
+        text.replace('\n', self.line_ending)
+    }
+
+    /// Records `text` as what is on disk now.
+    pub fn mark_saved(&mut self, text: String) {
+        self.saved = text;
+        self.dirty = false;
+    }
+
+    /// Takes `contents` read from disk unless there are unsaved edits. Text that matches what
+    /// was last saved is left alone so the cursor stays put after a save.
+    pub fn reload(&mut self, contents: &str) {
+        if self.dirty {
+            return;
+        }
+        let content = Content::with_text(contents);
+        let text = content.text();
+        if text == self.saved {
+            return;
+        }
+        self.content = content;
+        self.error = self.language.validate(&text);
+        self.saved = text;
+        self.line_ending = line_ending(contents);
+        self.trailing_newline = contents.ends_with('\n');
+    }
+
+    /// Rewrites a valid packet indented (`pretty`) or on one line; invalid JSON is left alone.
+    pub fn reformat(&mut self, pretty: bool) {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(&self.text()) else {
+            return;
+        };
+        let formatted = if pretty {
+            serde_json::to_string_pretty(&value)
+        } else {
+            serde_json::to_string(&value)
+        };
+        if let Ok(formatted) = formatted {
+            self.content = Content::with_text(&formatted);
+            self.changed();
+        }
+    }
+
+    /// Puts the cursor on the logical `line` and selects it.
+    pub fn go_to_line(&mut self, line: usize) {
+        self.content.perform(Action::Move(Motion::DocumentStart));
+        // `Down` moves by wrapped rows, so step until the cursor reaches the logical line
+        while self.content.cursor_position().0 < line {
+            let before = self.content.cursor_position();
+            self.content.perform(Action::Move(Motion::Down));
+            if self.content.cursor_position() == before {
+                break;
+            }
+        }
+        self.content.perform(Action::SelectLine);
+    }
+
+    pub fn highlight_settings(&self) -> highlight::Settings {
+        highlight::Settings {
+            language: self.language,
+            error: self.error.as_ref().map(|error| (error.line, error.column)),
+        }
+    }
+
+    fn changed(&mut self) {
+        let text = self.text();
+        self.dirty = text != self.saved;
+        self.error = self.language.validate(&text);
+    }
+}
+
+fn line_ending(contents: &str) -> &'static str {
+    if contents.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::widget::text_editor::Edit;
+
+    use super::*;
+
+    #[test]
+    fn tracks_validity_and_changes() {
+        let mut editor = FileEditor::new("{\n  \"a\": 1,\n  \"b\" 2\n}\n", Language::Json);
+        let error = editor.error.clone().unwrap();
+        assert_eq!((error.line, error.column), (2, 6));
+        assert!(!editor.dirty);
+
+        editor.content = Content::with_text("{\"b\": 2, \"a\": 1}");
+        editor.changed();
+        assert!(editor.dirty && editor.error.is_none());
+        editor.reformat(true);
+        assert_eq!(editor.text(), "{\n  \"b\": 2,\n  \"a\": 1\n}\n");
+
+        let editor = FileEditor::new("{\"é\": 1 2}", Language::Json);
+        assert_eq!(editor.error.unwrap().column, 8);
+
+        let long = "x".repeat(500);
+        let mut editor = FileEditor::new(&format!("{long}\n{long}\nthird\n"), Language::Plain);
+        editor.go_to_line(2);
+        assert_eq!(editor.content.cursor_position().0, 2);
+        assert_eq!(editor.content.selection().as_deref(), Some("third"));
+
+        let editor = FileEditor::new("version 1\nsend\n", Language::Procedure);
+        let error = editor.error.unwrap();
+        assert_eq!((error.line, error.column), (1, 4));
+    }
+
+    #[test]
+    fn keeps_line_endings_and_final_newline() {
+        for contents in ["{\r\n  \"a\": 1\r\n}\r\n", "{\r\n}", "{\n}", "{}\n", ""] {
+            let editor = FileEditor::new(contents, Language::Json);
+            assert_eq!(editor.file_contents(&editor.text()), contents);
+        }
+
+        let mut editor = FileEditor::new("a\r\nb", Language::Plain);
+        editor.perform(Action::Move(Motion::DocumentEnd));
+        editor.perform(Action::Edit(Edit::Enter));
+        editor.perform(Action::Edit(Edit::Insert('c')));
+        assert_eq!(editor.file_contents(&editor.text()), "a\r\nb\r\nc");
+    }
+}
@@ -0,0 +1,140 @@
+//! Details about a project file shown in the inspector.
+
+use std::path::{Path, PathBuf};
+
+use crate::{DispatchError, procedure};
+
+/// A file opened from the tree, with its contents as last read from disk.
+#[derive(Clone, Debug)]
+pub struct OpenedFile {
+    pub path: PathBuf,
+    /// Tree key of the file, see [`crate::file_tree::TreeEntry::key`].
+    pub key: String,
+    pub contents: String,
+    pub details: FileDetails,
+}
+
+impl OpenedFile {
+    pub fn read(path: &Path, key: String) -> Result<Self, DispatchError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| DispatchError::io(format!("reading {key}"), e))?;
+        let metadata =
+            std::fs::metadata(path).map_err(|e| DispatchError::io(format!("reading {key}"), e))?;
+        let details = FileDetails {
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|age| age.as_secs()),
+            summary: FileSummary::of(path, &contents),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            key,
+            contents,
+            details,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FileDetails {
+    /// Size in bytes.
+    pub size: u64,
+    /// Seconds since the Unix epoch.
+    pub modified: Option<u64>,
+    pub summary: FileSummary,
+}
+
+/// What the file holds, as far as it can be told from its type and contents.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FileSummary {
+    /// A JSON packet; `kind` is its top-level `"type"` field when there is one.
+    Packet {
+        kind: Option<String>,
+        fields: Vec<String>,
+    },
+    /// JSON that is not an object, e.g. an array of fixtures.
+    JsonValue {
+        description: String,
+    },
+    InvalidJson {
+        error: String,
+    },
+    /// A procedure that parses; `steps` excludes the version line.
+    Procedure {
+        steps: usize,
+    },
+    InvalidProcedure {
+        error: String,
+    },
+    Other,
+}
+
+impl FileSummary {
+    pub fn of(path: &Path, contents: &str) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::of_json(contents),
+            Some("proc") => match procedure::parse(contents) {
+                Ok(procedure) => Self::Procedure {
+                    steps: procedure.steps.len(),
+                },
+                Err(error) => Self::InvalidProcedure {
+                    error: error.to_string(),
+                },
+            },
+            _ => Self::Other,
+        }
+    }
+
+    fn of_json(contents: &str) -> Self {
+        match serde_json::from_str::<serde_json::Value>(contents) {
+            Ok(serde_json::Value::Object(object)) => Self::Packet {
+                kind: object
+                    .get("type")
+                    .and_then(|kind| kind.as_str())
+                    .map(str::to_string),
+                fields: object.keys().cloned().collect(),
+            },
+            Ok(serde_json::Value::Array(items)) => Self::JsonValue {
+                description: format!("array of {} item(s)", items.len()),
+            },
+            Ok(value) => Self::JsonValue {
+                description: format!("single value {value}"),
+            },
+            Err(error) => Self::InvalidJson {
+                error: error.to_string(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_packets_and_procedures() {
+        let packet = FileSummary::of(Path::new("login.json"), r#"{"type": "login", "user": "a"}"#);
+        assert_eq!(
+            packet,
+            FileSummary::Packet {
+                kind: Some("login".to_string()),
+                fields: vec!["type".to_string(), "user".to_string()],
+            }
+        );
+        assert!(matches!(
+            FileSummary::of(Path::new("broken.json"), "{"),
+            FileSummary::InvalidJson { .. }
+        ));
+        assert_eq!(
+            FileSummary::of(Path::new("a.proc"), "# comment\nversion 1\n\nconnect\n"),
+            FileSummary::Procedure { steps: 1 }
+        );
+        assert!(matches!(
+            FileSummary::of(Path::new("b.proc"), "connect\n"),
+            FileSummary::InvalidProcedure { .. }
+        ));
+    }
+}
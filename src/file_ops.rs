@@ -0,0 +1,217 @@
+//! Creating, renaming, duplicating and deleting files and folders inside a project.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    DispatchError,
+    config::unix_now,
+    project::{self, TRASH_DIR},
+};
+
+/// Starting contents of a packet created from the tree.
+pub const NEW_PACKET: &str = "{\n  \"type\": \"\"\n}\n";
+
+/// Starting contents of a procedure created from the tree.
+pub const NEW_PROCEDURE: &str = "version 1\nconnect\ndisconnect\n";
+
+/// Checks that `name` can be used for an entry in `dir`.
+///
+/// `current` is the entry being renamed, which may keep its own name or change its case.
+pub fn validate_entry_name(name: &str, dir: &Path, current: Option<&Path>) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Name cannot be empty".to_string());
+    }
+    if name.contains(['/', '\\']) {
+        return Err("Name cannot contain slashes".to_string());
+    }
+    if name.starts_with('.') {
+        return Err("Name cannot start with a dot".to_string());
+    }
+    if name != name.trim() {
+        return Err("Name cannot start or end with spaces".to_string());
+    }
+
+    let siblings: Vec<String> = std::fs::read_dir(dir)
+        .map_err(|e| format!("Cannot read {}: {e}", dir.display()))?
+        .flatten()
+        .filter(|entry| current.is_none_or(|current| entry.path() != current))
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    match project::find_colliding_project(name, &siblings) {
+        Some(existing) => Err(format!("'{existing}' already exists here")),
+        None => Ok(()),
+    }
+}
+
+/// Appends `.{extension}` unless `name` already ends with it.
+pub fn with_extension(name: &str, extension: &str) -> String {
+    if Path::new(name)
+        .extension()
+        .is_some_and(|ext| ext == extension)
+    {
+        name.to_string()
+    } else {
+        format!("{name}.{extension}")
+    }
+}
+
+/// Creates a new file; fails rather than overwrite an existing one.
+pub fn create_file(path: &Path, contents: &str) -> Result<(), DispatchError> {
+    use std::io::Write;
+
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|e| DispatchError::io(format!("creating {}", path.display()), e))
+}
+
+pub fn create_folder(path: &Path) -> Result<(), DispatchError> {
+    std::fs::create_dir(path)
+        .map_err(|e| DispatchError::io(format!("creating {}", path.display()), e))
+}
+
+/// Renames `path` within its folder and returns the new path.
+pub fn rename_entry(path: &Path, new_name: &str) -> Result<PathBuf, DispatchError> {
+    let target = sibling(path, new_name);
+    // A case-only rename on a case-insensitive filesystem sees the target as existing
+    let case_change = target
+        .to_string_lossy()
+        .eq_ignore_ascii_case(&path.to_string_lossy());
+    if target.exists() && !case_change {
+        return Err(DispatchError::Project(format!(
+            "'{new_name}' already exists"
+        )));
+    }
+    std::fs::rename(path, &target)
+        .map_err(|e| DispatchError::io(format!("renaming {}", path.display()), e))?;
+    Ok(target)
+}
+
+/// Copies a file or folder next to itself under `new_name` and returns the copy's path.
+pub fn duplicate_entry(path: &Path, new_name: &str) -> Result<PathBuf, DispatchError> {
+    let target = sibling(path, new_name);
+    if target.exists() {
+        return Err(DispatchError::Project(format!(
+            "'{new_name}' already exists"
+        )));
+    }
+    if path.is_dir() {
+        project::copy_dir_all(path, &target)?;
+    } else {
+        std::fs::copy(path, &target)
+            .map_err(|e| DispatchError::io(format!("copying {}", path.display()), e))?;
+    }
+    Ok(target)
+}
+
+/// Moves an entry into the project's hidden trash folder rather than deleting it outright.
+pub fn trash_entry(project_dir: &Path, path: &Path) -> Result<PathBuf, DispatchError> {
+    let trash = project_dir.join(TRASH_DIR);
+    std::fs::create_dir_all(&trash)
+        .map_err(|e| DispatchError::io("creating the project trash folder", e))?;
+    let name = entry_name(path);
+    let trashed = trash.join(format!("{name}-{}", unix_now()));
+    std::fs::rename(path, &trashed)
+        .map_err(|e| DispatchError::io(format!("moving {} to the trash", path.display()), e))?;
+    Ok(trashed)
+}
+
+/// Moves `path` into the folder `dir` and returns its new path.
+pub fn move_entry(path: &Path, dir: &Path) -> Result<PathBuf, DispatchError> {
+    if dir.starts_with(path) {
+        return Err(DispatchError::Project(format!(
+            "Cannot move {} into itself",
+            path.display()
+        )));
+    }
+    let name = entry_name(path);
+    validate_entry_name(&name, dir, None).map_err(DispatchError::Project)?;
+    let target = dir.join(&name);
+    std::fs::rename(path, &target)
+        .map_err(|e| DispatchError::io(format!("moving {}", path.display()), e))?;
+    Ok(target)
+}
+
+/// Copies a file or folder from outside the project into `dir`, renaming the copy if the name
+/// is taken, and returns the copy's path.
+pub fn copy_into(source: &Path, dir: &Path) -> Result<PathBuf, DispatchError> {
+    if dir.starts_with(source) {
+        return Err(DispatchError::Project(format!(
+            "Cannot copy {} into itself",
+            source.display()
+        )));
+    }
+    let mut name = entry_name(source);
+    if validate_entry_name(&name, dir, None).is_err() {
+        name = copy_name(&name, dir);
+    }
+    let target = dir.join(name);
+    if source.is_dir() {
+        project::copy_dir_all(source, &target)?;
+    } else {
+        std::fs::copy(source, &target)
+            .map_err(|e| DispatchError::io(format!("copying {}", source.display()), e))?;
+    }
+    Ok(target)
+}
+
+/// A name for a copy of `name` that is free in `dir`: `name-copy.ext`, `name-copy-2.ext`, ...
+pub fn copy_name(name: &str, dir: &Path) -> String {
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (name, None),
+    };
+    let candidate = |suffix: String| match extension {
+        Some(extension) => format!("{stem}-copy{suffix}.{extension}"),
+        None => format!("{stem}-copy{suffix}"),
+    };
+
+    let mut name = candidate(String::new());
+    let mut counter = 2;
+    while dir.join(&name).exists() {
+        name = candidate(format!("-{counter}"));
+        counter += 1;
+    }
+    name
+}
+
+fn entry_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn sibling(path: &Path, name: &str) -> PathBuf {
+    path.parent().unwrap_or(path).join(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_validated_against_siblings() {
+        let dir = std::env::temp_dir().join(format!("tnet-dispatch-ops-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        create_file(&dir.join("login.json"), NEW_PACKET).unwrap();
+
+        assert!(validate_entry_name("LOGIN.json", &dir, None).is_err());
+        assert!(validate_entry_name("Login.json", &dir, Some(&dir.join("login.json"))).is_ok());
+        assert!(validate_entry_name("a/b", &dir, None).is_err());
+        assert!(validate_entry_name(".hidden", &dir, None).is_err());
+        assert_eq!(copy_name("login.json", &dir), "login-copy.json");
+        assert_eq!(with_extension("ping", "json"), "ping.json");
+
+        create_folder(&dir.join("auth")).unwrap();
+        let moved = move_entry(&dir.join("login.json"), &dir.join("auth")).unwrap();
+        assert_eq!(moved, dir.join("auth/login.json"));
+        assert!(move_entry(&dir.join("auth"), &dir.join("auth")).is_err());
+        let copied = copy_into(&moved, &dir.join("auth")).unwrap();
+        assert_eq!(copied, dir.join("auth/login-copy.json"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,430 @@
+//! The project file tree: a cached model of the project folder and which entries are visible
+//! given the expanded directories.
+//!
+//! Entries are identified by their path relative to the project root with `/` separators, so
+//! the expanded set saved in the manifest means the same thing on every platform.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::project::ProjectManifest;
+
+/// One row of the file tree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeEntry {
+    pub path: PathBuf,
+    /// Path relative to the project root, `/`-separated.
+    pub key: String,
+    /// Nesting level, 0 for entries directly in the project root.
+    pub depth: usize,
+    pub is_directory: bool,
+}
+
+impl TreeEntry {
+    pub fn name(&self) -> &str {
+        self.key.rsplit('/').next().unwrap_or(&self.key)
+    }
+}
+
+/// Keyboard navigation in the file tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TreeKey {
+    Up,
+    Down,
+    /// Collapses the directory under the cursor, or moves to its parent.
+    Left,
+    /// Expands the directory under the cursor, or moves into it.
+    Right,
+    /// Toggles the directory under the cursor.
+    Enter,
+}
+
+/// The key for `path` inside `root`.
+pub fn entry_key(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The key of the directory containing `key`, or `None` for top-level entries.
+pub fn parent_key(key: &str) -> Option<&str> {
+    key.rsplit_once('/').map(|(parent, _)| parent)
+}
+
+/// Which entries the tree shows, from the project manifest.
+#[derive(Clone, Debug, Default)]
+pub struct TreeFilter {
+    file_types: Vec<String>,
+    show_all_files: bool,
+    ignore: Option<Gitignore>,
+}
+
+impl TreeFilter {
+    /// Builds the filter for the project at `root`; invalid ignore patterns are skipped, as
+    /// the settings form rejects them before they reach the manifest.
+    pub fn new(root: &Path, manifest: &ProjectManifest) -> Self {
+        Self {
+            file_types: manifest.file_types.clone(),
+            show_all_files: manifest.show_all_files,
+            ignore: build_ignore(root, &manifest.ignore_patterns).ok(),
+        }
+    }
+
+    /// Whether `entry` and, for directories, everything below it is hidden.
+    fn ignores(&self, entry: &TreeEntry) -> bool {
+        self.ignore
+            .as_ref()
+            .is_some_and(|ignore| ignore.matched(&entry.path, entry.is_directory).is_ignore())
+    }
+
+    fn shows_file(&self, path: &Path) -> bool {
+        self.show_all_files || has_file_type(path, &self.file_types)
+    }
+}
+
+/// Checks `.gitignore`-style patterns, naming the first one that does not parse.
+pub fn validate_ignore_patterns(patterns: &[String]) -> Result<(), String> {
+    build_ignore(Path::new(""), patterns).map(|_| ())
+}
+
+fn build_ignore(root: &Path, patterns: &[String]) -> Result<Gitignore, String> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .map_err(|e| format!("Invalid ignore pattern '{pattern}': {e}"))?;
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// A directory's children as last read from disk.
+#[derive(Clone, Debug)]
+struct Node {
+    name: String,
+    is_directory: bool,
+}
+
+/// Every directory listing under the project root, read once and then kept current from
+/// filesystem events so views never touch the disk.
+#[derive(Clone, Debug, Default)]
+pub struct FileTree {
+    root: PathBuf,
+    /// Children of each known directory, keyed like [`TreeEntry::key`]; `""` is the root.
+    listings: HashMap<String, Vec<Node>>,
+}
+
+impl FileTree {
+    /// Reads the whole tree under `root`, skipping hidden entries.
+    pub fn scan(root: &Path) -> Self {
+        let mut tree = Self {
+            root: root.to_path_buf(),
+            listings: HashMap::new(),
+        };
+        tree.scan_dir(String::new());
+        tree
+    }
+
+    /// Entries shown when only the directories in `expanded` are open, depth-first with
+    /// directories before files, leaving out what `filter` hides.
+    pub fn visible_entries(
+        &self,
+        expanded: &BTreeSet<String>,
+        filter: &TreeFilter,
+    ) -> Vec<TreeEntry> {
+        let mut entries = Vec::new();
+        self.walk("", 0, &mut |entry| {
+            if filter.ignores(&entry) {
+                return false;
+            }
+            let descend = entry.is_directory && expanded.contains(&entry.key);
+            if entry.is_directory || filter.shows_file(&entry.path) {
+                entries.push(entry);
+            }
+            descend
+        });
+        entries
+    }
+
+    /// Every file `filter` lets through, whether or not its directory is expanded.
+    pub fn all_files(&self, filter: &TreeFilter) -> Vec<TreeEntry> {
+        let mut files = Vec::new();
+        self.walk("", 0, &mut |entry| {
+            if filter.ignores(&entry) {
+                return false;
+            }
+            if entry.is_directory {
+                return true;
+            }
+            if filter.shows_file(&entry.path) {
+                files.push(entry);
+            }
+            false
+        });
+        files
+    }
+
+    /// Keys of every directory in the tree, for "expand all".
+    pub fn all_directories(&self) -> BTreeSet<String> {
+        self.listings
+            .keys()
+            .filter(|key| !key.is_empty())
+            .cloned()
+            .collect()
+    }
+
+    /// Re-reads the directories affected by changes to `paths`, leaving the rest untouched.
+    pub fn apply_changes(&mut self, paths: &[PathBuf]) {
+        for path in paths {
+            let Ok(relative) = path.strip_prefix(&self.root) else {
+                continue;
+            };
+            let key = entry_key(&self.root, path);
+            if relative
+                .components()
+                .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+            {
+                continue;
+            }
+
+            // The nearest directory we already list decides what appeared or disappeared
+            let mut parent = parent_key(&key).unwrap_or_default().to_string();
+            while !parent.is_empty() && !self.listings.contains_key(&parent) {
+                parent = parent_key(&parent).unwrap_or_default().to_string();
+            }
+            self.refresh_listing(&parent);
+            // Contents of a directory that still exists may have changed too
+            if !key.is_empty() && self.listings.contains_key(&key) {
+                self.refresh_listing(&key);
+            }
+        }
+    }
+
+    fn path_of(&self, key: &str) -> PathBuf {
+        if key.is_empty() {
+            self.root.clone()
+        } else {
+            self.root.join(key)
+        }
+    }
+
+    fn child_key(parent: &str, name: &str) -> String {
+        if parent.is_empty() {
+            name.to_string()
+        } else {
+            format!("{parent}/{name}")
+        }
+    }
+
+    /// Lists `key` and everything below it.
+    fn scan_dir(&mut self, key: String) {
+        let listing = read_listing(&self.path_of(&key));
+        let subdirectories: Vec<_> = listing
+            .iter()
+            .filter(|node| node.is_directory)
+            .map(|node| Self::child_key(&key, &node.name))
+            .collect();
+        self.listings.insert(key, listing);
+        for subdirectory in subdirectories {
+            self.scan_dir(subdirectory);
+        }
+    }
+
+    /// Re-reads one directory, scanning new subdirectories and dropping vanished ones.
+    fn refresh_listing(&mut self, key: &str) {
+        let path = self.path_of(key);
+        if !path.is_dir() {
+            self.forget_subtree(key);
+            return;
+        }
+
+        let listing = read_listing(&path);
+        let old_directories: Vec<_> = self
+            .listings
+            .get(key)
+            .into_iter()
+            .flatten()
+            .filter(|node| node.is_directory)
+            .map(|node| Self::child_key(key, &node.name))
+            .collect();
+        let new_directories: Vec<_> = listing
+            .iter()
+            .filter(|node| node.is_directory)
+            .map(|node| Self::child_key(key, &node.name))
+            .collect();
+        self.listings.insert(key.to_string(), listing);
+
+        for gone in old_directories
+            .iter()
+            .filter(|old| !new_directories.contains(old))
+        {
+            self.forget_subtree(gone);
+        }
+        for added in new_directories {
+            if !self.listings.contains_key(&added) {
+                self.scan_dir(added);
+            }
+        }
+    }
+
+    fn forget_subtree(&mut self, key: &str) {
+        let prefix = format!("{key}/");
+        self.listings
+            .retain(|known, _| known != key && !known.starts_with(&prefix));
+    }
+
+    /// Calls `visit` for the children of `key` in display order, descending into directories
+    /// when it returns true.
+    fn walk(&self, key: &str, depth: usize, visit: &mut dyn FnMut(TreeEntry) -> bool) {
+        for node in self.listings.get(key).into_iter().flatten() {
+            let child_key = Self::child_key(key, &node.name);
+            let entry = TreeEntry {
+                path: self.path_of(&child_key),
+                key: child_key.clone(),
+                depth,
+                is_directory: node.is_directory,
+            };
+            if visit(entry) {
+                self.walk(&child_key, depth + 1, visit);
+            }
+        }
+    }
+}
+
+/// The non-hidden entries of `dir`, directories first, each sorted by name.
+fn read_listing(dir: &Path) -> Vec<Node> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut listing: Vec<_> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            (!name.starts_with('.')).then(|| Node {
+                is_directory: entry.file_type().is_ok_and(|file_type| file_type.is_dir()),
+                name,
+            })
+        })
+        .collect();
+    listing.sort_by(|a, b| {
+        b.is_directory
+            .cmp(&a.is_directory)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    listing
+}
+
+fn has_file_type(path: &Path, file_types: &[String]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| file_types.iter().any(|file_type| file_type == ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("tnet-dispatch-tree-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        root
+    }
+
+    fn keys(tree: &FileTree, expanded: &BTreeSet<String>) -> Vec<String> {
+        let filter = TreeFilter {
+            file_types: vec!["json".to_string()],
+            ..TreeFilter::default()
+        };
+        tree.visible_entries(expanded, &filter)
+            .into_iter()
+            .map(|entry| entry.key)
+            .collect()
+    }
+
+    #[test]
+    fn only_expanded_directories_are_listed() {
+        let root = temp_root("expand");
+        std::fs::create_dir_all(root.join("packets/auth")).unwrap();
+        std::fs::write(root.join("packets/auth/login.json"), "{}").unwrap();
+        std::fs::write(root.join("packets/ping.json"), "{}").unwrap();
+        std::fs::write(root.join("notes.txt"), "").unwrap();
+        let tree = FileTree::scan(&root);
+
+        assert_eq!(keys(&tree, &BTreeSet::new()), ["packets"]);
+        let expanded = BTreeSet::from(["packets".to_string()]);
+        assert_eq!(
+            keys(&tree, &expanded),
+            ["packets", "packets/auth", "packets/ping.json"]
+        );
+        assert_eq!(
+            keys(&tree, &tree.all_directories()),
+            [
+                "packets",
+                "packets/auth",
+                "packets/auth/login.json",
+                "packets/ping.json"
+            ]
+        );
+        assert_eq!(parent_key("packets/auth"), Some("packets"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn changes_update_only_affected_directories() {
+        let root = temp_root("changes");
+        std::fs::create_dir_all(root.join("packets")).unwrap();
+        let mut tree = FileTree::scan(&root);
+
+        std::fs::create_dir_all(root.join("packets/new/deep")).unwrap();
+        std::fs::write(root.join("packets/new/deep/a.json"), "{}").unwrap();
+        tree.apply_changes(&[root.join("packets/new/deep/a.json")]);
+        assert_eq!(
+            keys(&tree, &tree.all_directories()),
+            [
+                "packets",
+                "packets/new",
+                "packets/new/deep",
+                "packets/new/deep/a.json"
+            ]
+        );
+
+        std::fs::remove_dir_all(root.join("packets/new")).unwrap();
+        tree.apply_changes(&[root.join("packets/new")]);
+        assert_eq!(keys(&tree, &tree.all_directories()), ["packets"]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn filter_shows_all_files_except_ignored() {
+        let root = temp_root("filter");
+        std::fs::create_dir_all(root.join("build")).unwrap();
+        std::fs::write(root.join("build/out.json"), "{}").unwrap();
+        std::fs::write(root.join("README.md"), "").unwrap();
+        std::fs::write(root.join("capture.bin"), "").unwrap();
+        std::fs::write(root.join("ping.json"), "{}").unwrap();
+        let tree = FileTree::scan(&root);
+
+        let mut manifest = ProjectManifest::new("filter");
+        manifest.show_all_files = true;
+        manifest.ignore_patterns = vec!["build/".to_string(), "*.bin".to_string()];
+        let filter = TreeFilter::new(&root, &manifest);
+        let visible: Vec<_> = tree
+            .visible_entries(&tree.all_directories(), &filter)
+            .into_iter()
+            .map(|entry| entry.key)
+            .collect();
+        assert_eq!(visible, ["README.md", "ping.json"]);
+        assert!(validate_ignore_patterns(&["{a".to_string()]).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,65 @@
+//! Fuzzy matching for type-to-filter pickers.
+
+/// Scores `candidate` against `query`, or returns `None` if the query characters do not all
+/// appear in order (ignoring case).
+///
+/// Higher scores are better matches: consecutive characters and characters at the start of a
+/// word (after `-`, `_`, `.`, `/`, a space or a lowercase-to-uppercase change) count extra.
+/// An empty query matches everything with a score of zero.
+pub fn score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for query_char in query.chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..candidate.len()).find(|&i| chars_match(query_char, candidate[i]))?;
+
+        score += 1;
+        if previous_match.is_some_and(|previous| previous + 1 == found) {
+            score += 8;
+        }
+        if is_word_start(&candidate, found) {
+            score += 6;
+        }
+        // Skipped characters cost a little so tighter matches rank first
+        score -= (found - position) as i64;
+
+        previous_match = Some(found);
+        position = found + 1;
+    }
+
+    Some(score)
+}
+
+fn chars_match(query: char, candidate: char) -> bool {
+    query == candidate || query.to_lowercase().eq(candidate.to_lowercase())
+}
+
+fn is_word_start(chars: &[char], index: usize) -> bool {
+    let Some(previous) = index.checked_sub(1).map(|i| chars[i]) else {
+        return true;
+    };
+    matches!(previous, '-' | '_' | '.' | '/' | ' ')
+        || (previous.is_lowercase() && chars[index].is_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requires_characters_in_order() {
+        assert!(score("ath", "auth-flow").is_some());
+        assert!(score("AUTH", "auth-flow").is_some());
+        assert!(score("hta", "auth-flow").is_none());
+        assert_eq!(score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn prefers_word_starts_and_runs() {
+        let best = score("af", "auth-flow").unwrap();
+        assert!(best > score("af", "leaf-nodes").unwrap());
+        assert!(score("flow", "auth-flow").unwrap() > score("flow", "f-l-o-w").unwrap());
+    }
+}
@@ -0,0 +1,289 @@
+//! Syntax highlighting for packets and procedures in the editor, with the parse error marked.
+//!
+//! Neither JSON strings nor procedure steps span lines, so each line is tokenized on its own.
+
+use std::ops::Range;
+
+use iced::advanced::text::highlighter::{self, Highlighter};
+use iced::{Font, Theme};
+
+use crate::editor::Language;
+
+/// What to highlight; a change re-highlights the whole text.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Settings {
+    /// How tokens are found; plain text only gets the error marked.
+    pub language: Language,
+    /// Zero-based line and column of the parse error, the column counted in chars.
+    pub error: Option<(usize, usize)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Highlight {
+    Key,
+    String,
+    Number,
+    /// `true`, `false` or `null`.
+    Literal,
+    Punctuation,
+    /// A procedure step name or one of its words, like `within`.
+    Keyword,
+    Comment,
+    /// A `${name}` reference in a procedure.
+    Variable,
+    Error,
+}
+
+pub struct SourceHighlighter {
+    settings: Settings,
+    current_line: usize,
+}
+
+impl Highlighter for SourceHighlighter {
+    type Settings = Settings;
+    type Highlight = Highlight;
+    type Iterator<'a> = std::vec::IntoIter<(Range<usize>, Highlight)>;
+
+    fn new(settings: &Settings) -> Self {
+        Self {
+            settings: settings.clone(),
+            current_line: 0,
+        }
+    }
+
+    fn update(&mut self, new_settings: &Settings) {
+        self.settings = new_settings.clone();
+        self.current_line = 0;
+    }
+
+    fn change_line(&mut self, line: usize) {
+        self.current_line = self.current_line.min(line);
+    }
+
+    fn highlight_line(&mut self, line: &str) -> Self::Iterator<'_> {
+        let mut spans = match self.settings.language {
+            Language::Json => tokenize(line),
+            Language::Procedure => tokenize_procedure(line),
+            Language::Plain => Vec::new(),
+        };
+        if let Some((error_line, column)) = self.settings.error
+            && error_line == self.current_line
+            && !line.is_empty()
+        {
+            // From the error to the end of the line, replacing the tokens it covers
+            let start = line
+                .char_indices()
+                .nth(column)
+                .map_or_else(|| line.len() - last_char_len(line), |(index, _)| index);
+            spans.retain(|(range, _)| range.end <= start);
+            spans.push((start..line.len(), Highlight::Error));
+        }
+        self.current_line += 1;
+        spans.into_iter()
+    }
+
+    fn current_line(&self) -> usize {
+        self.current_line
+    }
+}
+
+/// Colors for each kind of token, from the theme's palette.
+pub fn to_format(highlight: &Highlight, theme: &Theme) -> highlighter::Format<Font> {
+    let palette = theme.extended_palette();
+    let color = match highlight {
+        Highlight::Key => palette.primary.base.color,
+        Highlight::String => palette.success.base.color,
+        Highlight::Number | Highlight::Literal => palette.secondary.strong.color,
+        Highlight::Punctuation | Highlight::Comment => palette.background.strong.color,
+        Highlight::Keyword => palette.primary.strong.color,
+        Highlight::Variable => palette.secondary.base.color,
+        Highlight::Error => palette.danger.base.color,
+    };
+    highlighter::Format {
+        color: Some(color),
+        font: None,
+    }
+}
+
+/// The JSON tokens of one line, in order and without overlaps.
+fn tokenize(line: &str) -> Vec<(Range<usize>, Highlight)> {
+    let mut spans = Vec::new();
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let kind = match c {
+            '"' => {
+                let mut escaped = false;
+                for (_, c) in chars.by_ref() {
+                    match c {
+                        '\\' if !escaped => escaped = true,
+                        '"' if !escaped => break,
+                        _ => escaped = false,
+                    }
+                }
+                let end = chars.peek().map_or(line.len(), |(index, _)| *index);
+                let is_key = line[end..].trim_start().starts_with(':');
+                spans.push((
+                    start..end,
+                    if is_key {
+                        Highlight::Key
+                    } else {
+                        Highlight::String
+                    },
+                ));
+                continue;
+            }
+            '{' | '}' | '[' | ']' | ',' | ':' => Highlight::Punctuation,
+            '-' | '0'..='9' => {
+                while chars
+                    .next_if(|(_, c)| {
+                        c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-')
+                    })
+                    .is_some()
+                {}
+                Highlight::Number
+            }
+            c if c.is_ascii_alphabetic() => {
+                while chars.next_if(|(_, c)| c.is_ascii_alphabetic()).is_some() {}
+                let end = chars.peek().map_or(line.len(), |(index, _)| *index);
+                if matches!(&line[start..end], "true" | "false" | "null") {
+                    Highlight::Literal
+                } else {
+                    continue;
+                }
+            }
+            _ => continue,
+        };
+        let end = chars.peek().map_or(line.len(), |(index, _)| *index);
+        spans.push((start..end, kind));
+    }
+    spans
+}
+
+/// The tokens of one procedure line: the step keyword, operator words, variables and values.
+fn tokenize_procedure(line: &str) -> Vec<(Range<usize>, Highlight)> {
+    let start = line.len() - line.trim_start().len();
+    if line[start..].starts_with('#') {
+        return vec![(start..line.len(), Highlight::Comment)];
+    }
+
+    let mut spans = Vec::new();
+    let mut words = line[start..].split_whitespace().map(|word| {
+        let offset = word.as_ptr() as usize - line.as_ptr() as usize;
+        (offset..offset + word.len(), word)
+    });
+    if let Some((range, _)) = words.next() {
+        spans.push((range, Highlight::Keyword));
+    }
+    for (range, word) in words {
+        // Words inside a quoted value that has spaces were already covered
+        if spans.last().is_some_and(|(last, _)| last.end > range.start) {
+            continue;
+        }
+        let (range, kind) = match word {
+            "within" | "contains" | "exists" | "==" | "!=" | "=" => (range, Highlight::Keyword),
+            "true" | "false" | "null" => (range, Highlight::Literal),
+            _ if word.starts_with("${") && word.ends_with('}') => (range, Highlight::Variable),
+            _ if word.starts_with('"') => {
+                let end = line[range.start + 1..]
+                    .find('"')
+                    .map_or(line.len(), |index| range.start + index + 2);
+                (range.start..end, Highlight::String)
+            }
+            _ if word.starts_with(|c: char| c.is_ascii_digit() || c == '-') => {
+                (range, Highlight::Number)
+            }
+            _ => continue,
+        };
+        spans.push((range, kind));
+    }
+    spans
+}
+
+fn last_char_len(line: &str) -> usize {
+    line.chars().next_back().map_or(0, char::len_utf8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizes_keys_values_and_punctuation() {
+        let line = r#"  "user": "a\"b", "n": -1.5e3, "ok": true }"#;
+        let kinds: Vec<_> = tokenize(line)
+            .into_iter()
+            .map(|(range, kind)| (&line[range], kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (r#""user""#, Highlight::Key),
+                (":", Highlight::Punctuation),
+                (r#""a\"b""#, Highlight::String),
+                (",", Highlight::Punctuation),
+                (r#""n""#, Highlight::Key),
+                (":", Highlight::Punctuation),
+                ("-1.5e3", Highlight::Number),
+                (",", Highlight::Punctuation),
+                (r#""ok""#, Highlight::Key),
+                (":", Highlight::Punctuation),
+                ("true", Highlight::Literal),
+                ("}", Highlight::Punctuation),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenizes_procedure_steps() {
+        let line = r#"  expect ${name} == "a b" within 2s"#;
+        let kinds: Vec<_> = tokenize_procedure(line)
+            .into_iter()
+            .map(|(range, kind)| (&line[range], kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("expect", Highlight::Keyword),
+                ("${name}", Highlight::Variable),
+                ("==", Highlight::Keyword),
+                (r#""a b""#, Highlight::String),
+                ("within", Highlight::Keyword),
+                ("2s", Highlight::Number),
+            ]
+        );
+        assert_eq!(tokenize_procedure(" # note"), [(1..7, Highlight::Comment)]);
+    }
+
+    #[test]
+    fn marks_the_error_from_its_column() {
+        let mut highlighter = SourceHighlighter::new(&Settings {
+            language: Language::Json,
+            error: Some((1, 9)),
+        });
+        assert!(
+            highlighter
+                .highlight_line("{")
+                .all(|(_, kind)| kind != Highlight::Error)
+        );
+        let spans: Vec<_> = highlighter.highlight_line(r#"  "a": 1 2"#).collect();
+        assert_eq!(spans.last(), Some(&(9..10, Highlight::Error)));
+
+        // Columns count chars, so multi-byte text before the error keeps the span on it
+        let mut highlighter = SourceHighlighter::new(&Settings {
+            language: Language::Json,
+            error: Some((0, 8)),
+        });
+        let line = r#"{"é": 1 2}"#;
+        let spans: Vec<_> = highlighter.highlight_line(line).collect();
+        assert_eq!(spans.last(), Some(&(9..line.len(), Highlight::Error)));
+
+        // A column past the end marks the last char
+        let mut highlighter = SourceHighlighter::new(&Settings {
+            language: Language::Plain,
+            error: Some((0, 40)),
+        });
+        let spans: Vec<_> = highlighter.highlight_line("ab").collect();
+        assert_eq!(spans, [(1..2, Highlight::Error)]);
+    }
+}
@@ -1,47 +1,235 @@
+use std::{path::PathBuf, time::Duration};
+
 use app::{Dispatcher, Message, View};
-use iced::{Element, Theme};
+use file_info::OpenedFile;
+use file_tree::{FileTree, TreeKey};
+use iced::{
+    Element, Event, Subscription, Task, Theme, event, keyboard, mouse, widget::text_input, window,
+};
+use paths::Paths;
+use project::{ProjectManifest, ProjectSummary};
+use states::{
+    ExistingProjectState, ExportProjectState, ImportProjectState, LayoutState, NewProjectState,
+    ProcedureRun, ProjectAction, ProjectSettingsState, ProjectState, TreeAction, TreeDrag,
+    Workspace,
+};
+use templates::ProjectTemplate;
 use thiserror::Error;
 
 pub mod app;
+pub mod bundle;
 pub mod config;
+pub mod editor;
+pub mod file_info;
+pub mod file_ops;
+pub mod file_tree;
+pub mod fuzzy;
+pub mod highlight;
+pub mod paths;
+pub mod procedure;
+pub mod project;
+pub mod runner;
+pub mod search;
 pub mod states;
+pub mod templates;
+pub mod themes;
 pub mod views;
+pub mod watcher;
 
 #[derive(Debug, Clone, Error)]
-pub enum DispatchError {}
+pub enum DispatchError {
+    #[error("I/O error while {context}: {message}")]
+    Io { context: String, message: String },
+    #[error("Configuration error: {0}")]
+    Config(String),
+    #[error("Project error: {0}")]
+    Project(String),
+    #[error("Network error: {0}")]
+    Network(String),
+}
+
+impl DispatchError {
+    /// Wraps an I/O error together with a short description of what was being done.
+    pub fn io(context: impl Into<String>, error: std::io::Error) -> Self {
+        Self::Io {
+            context: context.into(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<toml::de::Error> for DispatchError {
+    fn from(error: toml::de::Error) -> Self {
+        Self::Config(error.to_string())
+    }
+}
+
+impl From<toml::ser::Error> for DispatchError {
+    fn from(error: toml::ser::Error) -> Self {
+        Self::Config(error.to_string())
+    }
+}
+
+/// How long the layout must stay unchanged before it is written to the project manifest.
+const LAYOUT_SAVE_DELAY: Duration = Duration::from_millis(500);
+
+const USAGE: &str = "Usage: tnet-dispatch [--data-dir <DIR>]
+
+Options:
+  --data-dir <DIR>  Keep config and projects under DIR instead of the default location
+  -h, --help        Print this help";
 
 pub fn main() -> iced::Result {
+    let paths = match parse_args(std::env::args().skip(1)).and_then(Paths::resolve) {
+        Ok(paths) => paths,
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
     iced::application("Tnet Dispatcher", update, view)
         .theme(theme)
-        .run()
+        .subscription(subscription)
+        .run_with(move || (Dispatcher::new(paths), Task::none()))
+}
+
+/// Parses the command line, returning the `--data-dir` override if one was given.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<PathBuf>, DispatchError> {
+    let mut data_dir = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            "--data-dir" => {
+                let value = args.next().ok_or_else(|| {
+                    DispatchError::Config("--data-dir requires a directory".to_string())
+                })?;
+                data_dir = Some(PathBuf::from(value));
+            }
+            _ => match arg.strip_prefix("--data-dir=") {
+                Some(value) => data_dir = Some(PathBuf::from(value)),
+                None => {
+                    return Err(DispatchError::Config(format!("unknown argument '{arg}'")));
+                }
+            },
+        }
+    }
+    Ok(data_dir)
+}
+
+fn theme(state: &Dispatcher) -> Theme {
+    state.states.themes.active.clone()
+}
+
+fn subscription(state: &Dispatcher) -> Subscription<Message> {
+    let keys = match state.view {
+        View::SelectingExistingProject => keyboard::on_key_press(project_picker_key),
+        View::ProjectSelected => Subscription::batch([
+            keyboard::on_key_press(workspace_key),
+            event::listen_with(workspace_event),
+        ]),
+        _ => Subscription::none(),
+    };
+    // Every open tab is watched, not just the one in front, so switching back is instant
+    let watchers = state.states.tabs.open.iter().map(|workspace| {
+        watcher::watch_workspace(workspace.id, workspace.project.current_project_path.clone())
+    });
+    Subscription::batch(std::iter::once(keys).chain(watchers))
+}
+
+/// Ctrl+Tab and Ctrl+Shift+Tab switch between open project tabs, Ctrl+P opens the file finder
+/// and Escape closes it, Ctrl+Shift+F opens the search panel and Ctrl+S saves the opened file;
+/// arrow keys and Enter move through the file tree.
+fn workspace_key(key: keyboard::Key, modifiers: keyboard::Modifiers) -> Option<Message> {
+    use keyboard::key::Named;
+
+    let named = match key.as_ref() {
+        keyboard::Key::Character("p") if modifiers.command() => {
+            return Some(Message::OpenQuickOpen);
+        }
+        keyboard::Key::Character("s") if modifiers.command() => {
+            return Some(Message::SaveOpenedFile);
+        }
+        keyboard::Key::Character(c)
+            if modifiers.command() && modifiers.shift() && c.eq_ignore_ascii_case("f") =>
+        {
+            return Some(Message::ShowSearchPanel(true));
+        }
+        keyboard::Key::Named(named) => named,
+        _ => return None,
+    };
+    let tree_key = match named {
+        Named::Escape => return Some(Message::CloseQuickOpen),
+        Named::Tab if modifiers.control() => {
+            return Some(Message::CycleTabs(if modifiers.shift() { -1 } else { 1 }));
+        }
+        Named::ArrowUp => TreeKey::Up,
+        Named::ArrowDown => TreeKey::Down,
+        Named::ArrowLeft => TreeKey::Left,
+        Named::ArrowRight => TreeKey::Right,
+        Named::Enter => TreeKey::Enter,
+        _ => return None,
+    };
+    Some(Message::TreeKeyPressed(tree_key))
+}
+
+/// Files dragged in from the desktop, and mouse releases no tree row took, which end a drag
+/// in the file tree.
+fn workspace_event(event: Event, status: event::Status, _window: window::Id) -> Option<Message> {
+    match event {
+        Event::Window(window::Event::FileHovered(_)) => Some(Message::FilesHovered),
+        Event::Window(window::Event::FilesHoveredLeft) => Some(Message::FilesHoveredLeft),
+        Event::Window(window::Event::FileDropped(path)) => Some(Message::FileDropped(path)),
+        Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+            if status == event::Status::Ignored =>
+        {
+            Some(Message::CancelTreeDrag)
+        }
+        _ => None,
+    }
 }
 
-fn theme(_state: &Dispatcher) -> Theme {
-    Theme::CatppuccinMocha
+/// Arrow keys move through the project picker and Enter opens the selection.
+fn project_picker_key(key: keyboard::Key, _modifiers: keyboard::Modifiers) -> Option<Message> {
+    match key {
+        keyboard::Key::Named(keyboard::key::Named::ArrowUp) => {
+            Some(Message::MoveProjectSelection(-1))
+        }
+        keyboard::Key::Named(keyboard::key::Named::ArrowDown) => {
+            Some(Message::MoveProjectSelection(1))
+        }
+        keyboard::Key::Named(keyboard::key::Named::Enter) => Some(Message::SubmitProjectPicker),
+        _ => None,
+    }
 }
 
-fn view(state: &Dispatcher) -> Element<Message> {
-    if state.conf.first_time_use {
-        println!("First time onboarding");
+fn view(state: &Dispatcher) -> Element<'_, Message> {
+    let content = if state.conf.first_time_use {
         views::on_boarding(state)
     } else {
-        println!("view state was changed");
-
         match &state.view {
             View::Onboarding1 => views::on_boarding_2(state),
             View::ProjectSelected => views::project_selected(state),
             View::NoProjectSelected => views::no_project_selected(state),
             View::CreatingProject => views::creating_project(state),
             View::SelectingExistingProject => views::selecting_existing_project(state),
+            View::ProjectSettings => views::editing_project_settings(state),
+            View::ExportingProject => views::exporting_project(state),
+            View::ImportingProject => views::importing_project(state),
         }
-    }
+    };
+
+    views::with_error_banner(state, content)
 }
 
-fn update(app: &mut Dispatcher, message: Message) {
+fn update(app: &mut Dispatcher, message: Message) -> Task<Message> {
     match message {
         Message::ContinueOnboarding => {
             app.conf.first_time_use = false;
-            // app.conf.save().expect("Failed to save config");
+            app.save_config();
             app.view = View::Onboarding1;
         }
         Message::SelectedProject => {
@@ -51,63 +239,71 @@ fn update(app: &mut Dispatcher, message: Message) {
             app.view = View::NoProjectSelected;
         }
         Message::CreateNewProject => {
+            load_existing_projects(app, Message::CreateNewProject);
+            app.states.new_project.taken_names = app
+                .states
+                .existing_project
+                .available_projects
+                .iter()
+                .chain(&app.states.project_actions.archived_projects)
+                .cloned()
+                .collect();
+            match templates::available_templates(&app.conf.paths.config_dir) {
+                Ok(templates) => app.states.new_project.templates = templates,
+                Err(error) => {
+                    app.states.new_project.templates = ProjectTemplate::BUILT_IN.to_vec();
+                    app.report_error(error, None);
+                }
+            }
             app.view = View::CreatingProject;
         }
         Message::OpenExistingProject => {
+            // Start from an empty filter but remember the chosen sort order
+            app.states.existing_project = ExistingProjectState {
+                sort: app.states.existing_project.sort,
+                ..ExistingProjectState::default()
+            };
             app.view = View::SelectingExistingProject;
-            app.states.existing_project.available_projects = app.conf.get_available_projects();
+            load_existing_projects(app, Message::OpenExistingProject);
+            return text_input::focus(views::project::PROJECT_FILTER_ID.clone());
         }
         Message::NewProjectNameChanged(name) => {
-            app.states.new_project.project_name = name.clone();
-
-            // Validate project name: no spaces or special characters allowed
-            if name.contains(' ') {
-                app.states.new_project.validation_error =
-                    Some("Project name cannot contain spaces".to_string());
-            } else if !name
-                .chars()
-                .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
-            {
-                app.states.new_project.validation_error = Some(
-                    "Project name can only contain letters, numbers, underscores and hyphens"
-                        .to_string(),
-                );
-            } else if name.is_empty() {
-                app.states.new_project.validation_error =
-                    Some("Project name cannot be empty".to_string());
-            } else {
-                app.states.new_project.validation_error = None;
+            // Validate project name: allowed characters, length, reserved names and collisions
+            let new_project = &mut app.states.new_project;
+            new_project.validation_error =
+                project::validate_project_name(&name, &new_project.taken_names).err();
+            new_project.existing_match = project::find_colliding_project(
+                &name,
+                &app.states.existing_project.available_projects,
+            )
+            .cloned();
+            new_project.project_name = name;
+        }
+        Message::NewProjectTemplateSelected(template) => {
+            app.states.new_project.template = template;
+        }
+        Message::OpenCollidingProject => {
+            if let Some(existing) = app.states.new_project.existing_match.clone() {
+                match open_project(app, &existing) {
+                    Ok(()) => app.states.new_project = NewProjectState::default(),
+                    Err(error) => app.report_error(error, Some(Message::OpenCollidingProject)),
+                }
             }
         }
         Message::CancelNewProject => {
-            app.states.new_project.project_name = String::new();
-            app.states.new_project.validation_error = None;
+            app.states.new_project = NewProjectState::default();
             app.view = View::NoProjectSelected;
         }
         Message::ConfirmNewProject => {
             if app.states.new_project.validation_error.is_none()
                 && !app.states.new_project.project_name.is_empty()
+                && let Err(error) = create_new_project(app)
             {
-                app.states.project.current_project = app.states.new_project.project_name.clone();
-                
-                std::fs::create_dir_all(
-                    app.conf
-                        .get_data_path()
-                        .join(&app.states.project.current_project),
-                )
-                .expect("Failed to create project directory");
-                app.states.project.current_project_path = app
-                    .conf
-                    .get_data_path()
-                    .join(&app.states.project.current_project);
-
-                app.states.new_project.project_name = String::new();
-                app.states.new_project.validation_error = None;
-                app.view = View::ProjectSelected;
+                app.report_error(error, Some(Message::ConfirmNewProject));
             }
         }
         Message::LoadExistingProjects => {
-            app.states.existing_project.available_projects = app.conf.get_available_projects();
+            load_existing_projects(app, Message::LoadExistingProjects);
         }
         Message::ExistingProjectsLoaded(projects) => {
             app.states.existing_project.available_projects = projects;
@@ -115,29 +311,1207 @@ fn update(app: &mut Dispatcher, message: Message) {
         Message::SelectExistingProject(project_name) => {
             app.states.existing_project.selected_project = Some(project_name);
         }
-        Message::ToggleProjectDropdown => {
-            app.states.existing_project.is_dropdown_open = !app.states.existing_project.is_dropdown_open;
+        Message::ProjectFilterChanged(query) => {
+            let picker = &mut app.states.existing_project;
+            picker.query = query;
+            // Keep the selection on a visible project so Enter opens what is shown
+            picker.selected_project = picker
+                .visible_projects(&app.conf.recent_projects)
+                .first()
+                .map(|summary| summary.name.clone());
+            app.states.project_actions.reset_prompt();
+        }
+        Message::ProjectSortChanged(sort) => {
+            app.states.existing_project.sort = sort;
+        }
+        Message::MoveProjectSelection(offset) => {
+            app.states
+                .existing_project
+                .move_selection(&app.conf.recent_projects, offset);
+            app.states.project_actions.reset_prompt();
+        }
+        Message::SubmitProjectPicker => {
+            let picker = &app.states.existing_project;
+            let visible = picker.visible_projects(&app.conf.recent_projects);
+            let target = picker
+                .selected_project
+                .as_ref()
+                .filter(|selected| visible.iter().any(|summary| &summary.name == *selected))
+                .or_else(|| visible.first().map(|summary| &summary.name))
+                .cloned();
+            if let Some(project_name) = target {
+                app.states.existing_project.selected_project = Some(project_name);
+                return update(app, Message::ConfirmSelectedProject);
+            }
         }
         Message::ConfirmSelectedProject => {
-            if let Some(project_name) = &app.states.existing_project.selected_project {
-                app.states.project.current_project = project_name.clone();
-                app.states.project.current_project_path = app.conf.get_data_path().join(project_name);
-                app.view = View::ProjectSelected;
+            if let Some(project_name) = app.states.existing_project.selected_project.clone()
+                && let Err(error) = open_project(app, &project_name)
+            {
+                app.report_error(error, Some(Message::ConfirmSelectedProject));
             }
         }
         Message::CancelProjectSelection => {
-            app.states.existing_project.selected_project = None;
-            app.states.existing_project.is_dropdown_open = false;
+            app.states.existing_project = ExistingProjectState::default();
+            app.states.project_actions.reset_prompt();
+            app.view = View::NoProjectSelected;
+        }
+        // Project lifecycle messages
+        Message::StartProjectAction(action) => {
+            app.states.project_actions.reset_prompt();
+            app.states.project_actions.name_input = app
+                .states
+                .existing_project
+                .selected_project
+                .clone()
+                .unwrap_or_default();
+            if action == ProjectAction::Duplicate {
+                app.states.project_actions.name_input.push_str("-copy");
+            }
+            app.states.project_actions.pending = Some(action);
+            validate_project_action_name(app);
+        }
+        Message::ProjectActionNameChanged(name) => {
+            app.states.project_actions.name_input = name;
+            validate_project_action_name(app);
+        }
+        Message::ConfirmProjectAction => {
+            if app.states.project_actions.validation_error.is_none()
+                && let Err(error) = run_project_action(app)
+            {
+                app.report_error(error, Some(Message::ConfirmProjectAction));
+            }
+        }
+        Message::CancelProjectAction => {
+            app.states.project_actions.reset_prompt();
+        }
+        Message::RequestDeleteProject => {
+            app.states.project_actions.reset_prompt();
+            app.states.project_actions.confirming_delete = true;
+        }
+        Message::ConfirmDeleteProject => {
+            if let Some(name) = app.states.existing_project.selected_project.clone() {
+                match app
+                    .conf
+                    .data_path_for_move(&name)
+                    .and_then(|data_path| project::trash_project(&data_path, &name))
+                {
+                    Ok(trashed) => {
+                        app.states.project_actions.last_trashed = Some(trashed);
+                        forget_project(app, &name);
+                    }
+                    Err(error) => app.report_error(error, Some(Message::ConfirmDeleteProject)),
+                }
+            }
+            app.states.project_actions.confirming_delete = false;
+        }
+        Message::UndoDeleteProject => {
+            if let Some(trashed) = app.states.project_actions.last_trashed.take() {
+                match app
+                    .conf
+                    .get_data_path()
+                    .and_then(|data_path| project::restore_project(&data_path, &trashed))
+                {
+                    Ok(()) => {
+                        app.states.existing_project.selected_project = Some(trashed.name);
+                        load_existing_projects(app, Message::LoadExistingProjects);
+                    }
+                    Err(error) => {
+                        app.states.project_actions.last_trashed = Some(trashed);
+                        app.report_error(error, Some(Message::UndoDeleteProject));
+                    }
+                }
+            }
+        }
+        Message::ArchiveProject => {
+            if let Some(name) = app.states.existing_project.selected_project.clone() {
+                match app
+                    .conf
+                    .data_path_for_move(&name)
+                    .and_then(|data_path| project::archive_project(&data_path, &name))
+                {
+                    Ok(()) => forget_project(app, &name),
+                    Err(error) => app.report_error(error, Some(Message::ArchiveProject)),
+                }
+            }
+        }
+        Message::UnarchiveProject(name) => {
+            match app
+                .conf
+                .get_data_path()
+                .and_then(|data_path| project::unarchive_project(&data_path, &name))
+            {
+                Ok(()) => {
+                    app.states.existing_project.selected_project = Some(name);
+                    load_existing_projects(app, Message::LoadExistingProjects);
+                }
+                Err(error) => app.report_error(error, Some(Message::UnarchiveProject(name))),
+            }
+        }
+        Message::ToggleShowArchived => {
+            app.states.project_actions.show_archived = !app.states.project_actions.show_archived;
+        }
+        Message::FolderPathChanged(path) => {
+            app.states.existing_project.folder_path = path;
+        }
+        Message::OpenFolder => {
+            let folder = app.cwd.join(app.states.existing_project.folder_path.trim());
+            match app
+                .conf
+                .link_project(&folder)
+                .and_then(|name| open_project(app, &name))
+            {
+                Ok(()) => app.states.existing_project = ExistingProjectState::default(),
+                Err(error) => app.report_error(error, Some(Message::OpenFolder)),
+            }
+        }
+        Message::UnlinkProject => {
+            if let Some(name) = app.states.existing_project.selected_project.clone() {
+                app.conf.unlink_project(&name);
+                app.save_config();
+                forget_project(app, &name);
+            }
+        }
+        Message::OpenRecentProject(project_name) => {
+            if let Err(error) = open_project(app, &project_name) {
+                app.report_error(error, Some(Message::OpenRecentProject(project_name)));
+                app.prune_recent_projects();
+            }
+        }
+        Message::TogglePinnedRecentProject(project_name) => {
+            if let Some(recent) = app
+                .conf
+                .recent_projects
+                .iter_mut()
+                .find(|recent| recent.name == project_name)
+            {
+                recent.pinned = !recent.pinned;
+                app.save_config();
+            }
+        }
+        Message::RemoveRecentProject(project_name) => {
+            app.conf
+                .recent_projects
+                .retain(|recent| recent.name != project_name);
+            app.save_config();
+        }
+        // Project settings messages
+        Message::OpenProjectSettings => {
+            if let Some(workspace) = app.states.tabs.active() {
+                app.states.project_settings =
+                    ProjectSettingsState::from_manifest(&workspace.project.manifest);
+                app.view = View::ProjectSettings;
+            }
+        }
+        Message::ProjectSettingsNameChanged(name) => {
+            app.states.project_settings.name = name;
+        }
+        Message::ProjectSettingsDescriptionChanged(description) => {
+            app.states.project_settings.description = description;
+        }
+        Message::ProjectSettingsTagsChanged(tags) => {
+            app.states.project_settings.tags = tags;
+        }
+        Message::ProjectSettingsTargetChanged(target) => {
+            app.states.project_settings.default_target = target;
+        }
+        Message::ProjectSettingsFileTypesChanged(file_types) => {
+            app.states.project_settings.file_types = file_types;
+        }
+        Message::ProjectSettingsIgnorePatternsChanged(patterns) => {
+            app.states.project_settings.ignore_patterns = patterns;
+        }
+        Message::SaveProjectSettings => {
+            let Some(workspace) = app.states.tabs.active_mut() else {
+                return Task::none();
+            };
+            let project = &mut workspace.project;
+            match app.states.project_settings.apply_to(&mut project.manifest) {
+                Ok(()) => {
+                    app.states.project_settings.validation_error = None;
+                    let saved = project.manifest.save(&project.current_project_path);
+                    workspace.refresh_tree_filter();
+                    match saved {
+                        Ok(()) => app.view = View::ProjectSelected,
+                        Err(error) => app.report_error(error, Some(Message::SaveProjectSettings)),
+                    }
+                }
+                Err(validation_error) => {
+                    app.states.project_settings.validation_error = Some(validation_error);
+                }
+            }
+        }
+        Message::SaveProjectAsTemplate(replace) => {
+            let Some(workspace) = app.states.tabs.active() else {
+                return Task::none();
+            };
+            let project = &workspace.project;
+            let settings = &mut app.states.project_settings;
+            if !replace
+                && templates::template_exists(&app.conf.paths.config_dir, &project.current_project)
+            {
+                settings.confirm_template_replace = true;
+                return Task::none();
+            }
+            match templates::save_as_template(
+                &project.current_project_path,
+                &project.current_project,
+                &project.manifest,
+                &app.conf.paths.config_dir,
+                replace,
+            ) {
+                Ok(()) => {
+                    settings.saved_as_template = true;
+                    settings.confirm_template_replace = false;
+                }
+                Err(error) => {
+                    app.report_error(error, Some(Message::SaveProjectAsTemplate(replace)))
+                }
+            }
+        }
+        Message::CancelProjectSettings => {
+            app.states.project_settings = ProjectSettingsState::default();
+            app.view = View::ProjectSelected;
+        }
+        // Bundle export/import messages
+        Message::OpenExportProject => {
+            let Some(workspace) = app.states.tabs.active() else {
+                return Task::none();
+            };
+            let file_name = format!(
+                "{}.{}",
+                workspace.project.current_project,
+                bundle::BUNDLE_EXTENSION
+            );
+            app.states.export_project = ExportProjectState {
+                destination: app.cwd.join(file_name).display().to_string(),
+                ..ExportProjectState::default()
+            };
+            app.view = View::ExportingProject;
+        }
+        Message::ExportDestinationChanged(destination) => {
+            app.states.export_project.destination = destination;
+            app.states.export_project.exported_to = None;
+        }
+        Message::ExportIncludeHistoryToggled(include_history) => {
+            app.states.export_project.include_history = include_history;
+        }
+        Message::ConfirmExportProject => {
+            let Some(workspace) = app.states.tabs.active() else {
+                return Task::none();
+            };
+            let destination = app.cwd.join(&app.states.export_project.destination);
+            match bundle::export_project(
+                &workspace.project.current_project_path,
+                &workspace.project.current_project,
+                &workspace.project.manifest,
+                &destination,
+                app.states.export_project.include_history,
+            ) {
+                Ok(()) => app.states.export_project.exported_to = Some(destination),
+                Err(error) => app.report_error(error, Some(Message::ConfirmExportProject)),
+            }
+        }
+        Message::CloseExportProject => {
+            app.states.export_project = ExportProjectState::default();
+            app.view = View::ProjectSelected;
+        }
+        Message::OpenImportProject => {
+            app.states.import_project = ImportProjectState::default();
+            app.view = View::ImportingProject;
+        }
+        Message::ImportSourceChanged(source) => {
+            app.states.import_project.source = source;
+        }
+        Message::ConfirmImportProject => {
+            if let Err(error) = import_project(app) {
+                app.report_error(error, Some(Message::ConfirmImportProject));
+            }
+        }
+        Message::CancelImportProject => {
+            app.states.import_project = ImportProjectState::default();
             app.view = View::NoProjectSelected;
         }
         // Handle panel resize messages
         Message::ResizeHorizontal(ratio) => {
-            // Update the horizontal ratio (clamped between 0.2 and 0.9)
-            app.states.layout.horizontal_ratio = ratio.max(0.2).min(0.9);
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                // Update the horizontal ratio (clamped between 0.2 and 0.9)
+                workspace.layout.horizontal_ratio = ratio.clamp(0.2, 0.9);
+                return schedule_layout_save(workspace);
+            }
         }
         Message::ResizeVertical(ratio) => {
-            // Update the vertical ratio (clamped between 0.2 and 0.9)
-            app.states.layout.vertical_ratio = ratio.max(0.2).min(0.9);
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                // Update the vertical ratio (clamped between 0.2 and 0.9)
+                workspace.layout.vertical_ratio = ratio.clamp(0.2, 0.9);
+                return schedule_layout_save(workspace);
+            }
+        }
+        Message::ToggleFileTree => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.layout.file_tree_collapsed = !workspace.layout.file_tree_collapsed;
+                return schedule_layout_save(workspace);
+            }
+        }
+        Message::ToggleInspector => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.layout.inspector_collapsed = !workspace.layout.inspector_collapsed;
+                return schedule_layout_save(workspace);
+            }
+        }
+        Message::ResetLayout => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                // Panel sizes go back to the defaults; the tree stays as the user opened it
+                workspace.layout = LayoutState {
+                    revision: workspace.layout.revision,
+                    expanded_dirs: std::mem::take(&mut workspace.layout.expanded_dirs),
+                    ..LayoutState::default()
+                };
+                return schedule_layout_save(workspace);
+            }
+        }
+        Message::PersistLayout(id, revision) => {
+            if let Some(workspace) = app.states.tabs.due_layout_save(id, revision)
+                && let Err(error) = persist_layout(workspace)
+            {
+                app.report_error(error, Some(Message::PersistLayout(id, revision)));
+            }
+        }
+        // File tree messages
+        Message::ToggleDirectory(key) => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.toggle_directory(&key);
+                return schedule_layout_save(workspace);
+            }
+        }
+        Message::ExpandAllDirectories => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.layout.expanded_dirs = workspace.tree.all_directories();
+                return schedule_layout_save(workspace);
+            }
+        }
+        Message::CollapseAllDirectories => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.layout.expanded_dirs.clear();
+                workspace.tree_cursor = None;
+                return schedule_layout_save(workspace);
+            }
+        }
+        Message::ShowAllFilesToggled(show_all_files) => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                let project = &mut workspace.project;
+                project.manifest.show_all_files = show_all_files;
+                let saved = project.manifest.save(&project.current_project_path);
+                workspace.refresh_tree_filter();
+                if let Err(error) = saved {
+                    app.report_error(error, Some(Message::ShowAllFilesToggled(show_all_files)));
+                }
+            }
+        }
+        Message::TreeKeyPressed(key) => {
+            // The file finder takes the keys while it is showing
+            if let Some(workspace) = app.states.tabs.active()
+                && workspace.quick_open.is_some()
+            {
+                return match key {
+                    TreeKey::Up => update(app, Message::MoveQuickOpenSelection(-1)),
+                    TreeKey::Down => update(app, Message::MoveQuickOpenSelection(1)),
+                    TreeKey::Enter => update(app, Message::SubmitQuickOpen),
+                    TreeKey::Left | TreeKey::Right => Task::none(),
+                };
+            }
+            if let Some(workspace) = app.states.tabs.active_mut()
+                && !workspace.layout.file_tree_collapsed
+            {
+                if key == TreeKey::Enter
+                    && let Some(path) = workspace.cursor_file()
+                {
+                    return update(app, Message::FileSelected(path));
+                }
+                if workspace.navigate_tree(key) {
+                    return schedule_layout_save(workspace);
+                }
+            }
+        }
+        Message::FileSelected(path) => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                let key = file_tree::entry_key(&workspace.project.current_project_path, &path);
+                let already_open = workspace
+                    .opened_file
+                    .as_ref()
+                    .is_some_and(|opened| opened.path == path);
+                if let Some(opened) = &workspace.opened_file
+                    && !already_open
+                    && workspace.editor.as_ref().is_some_and(|editor| editor.dirty)
+                {
+                    let error = DispatchError::Project(format!(
+                        "{} has unsaved changes; save or revert them first",
+                        opened.key
+                    ));
+                    app.report_error(error, None);
+                    return Task::none();
+                }
+                if already_open {
+                    workspace.tree_cursor = Some(key);
+                    return Task::none();
+                }
+                match OpenedFile::read(&path, key.clone()) {
+                    Ok(opened) => {
+                        workspace.show_file(opened);
+                        workspace.remember_recent_file(&key);
+                        workspace.tree_cursor = Some(key);
+                    }
+                    Err(error) => app.report_error(error, Some(Message::FileSelected(path))),
+                }
+            }
+        }
+        // Editor messages
+        Message::EditorAction(action) => {
+            if let Some(editor) = app
+                .states
+                .tabs
+                .active_mut()
+                .and_then(|workspace| workspace.editor.as_mut())
+            {
+                editor.perform(action);
+            }
+        }
+        Message::ReformatJson(pretty) => {
+            if let Some(editor) = app
+                .states
+                .tabs
+                .active_mut()
+                .and_then(|workspace| workspace.editor.as_mut())
+            {
+                editor.reformat(pretty);
+            }
+        }
+        Message::SaveOpenedFile => {
+            if let Some(workspace) = app.states.tabs.active_mut()
+                && let (Some(opened), Some(editor)) =
+                    (&workspace.opened_file, &mut workspace.editor)
+                && editor.dirty
+            {
+                let text = editor.text();
+                let path = opened.path.clone();
+                let saved = std::fs::write(&path, editor.file_contents(&text))
+                    .map_err(|e| DispatchError::io(format!("saving {}", opened.key), e));
+                match saved {
+                    Ok(()) => {
+                        editor.mark_saved(text);
+                        // Picks up the new size and summary for the inspector
+                        if let Err(error) = workspace.refresh_opened_file(&[path]) {
+                            app.report_error(error, None);
+                        }
+                    }
+                    Err(error) => app.report_error(error, Some(Message::SaveOpenedFile)),
+                }
+            }
+        }
+        Message::RevertOpenedFile => {
+            if let Some(workspace) = app.states.tabs.active_mut()
+                && let Some(opened) = &workspace.opened_file
+            {
+                match OpenedFile::read(&opened.path, opened.key.clone()) {
+                    Ok(opened) => workspace.show_file(opened),
+                    Err(error) => app.report_error(error, Some(Message::RevertOpenedFile)),
+                }
+            }
+        }
+        Message::RunProcedure => {
+            let Some(workspace) = app.states.tabs.active_mut() else {
+                return Task::none();
+            };
+            let (Some(opened), Some(editor)) = (&workspace.opened_file, &workspace.editor) else {
+                return Task::none();
+            };
+            let source = editor.text();
+            let procedure = match procedure::parse(&source) {
+                Ok(procedure) => procedure,
+                Err(error) => {
+                    let error = DispatchError::Project(error.in_file(&opened.key));
+                    app.report_error(error, None);
+                    return Task::none();
+                }
+            };
+            let id = workspace.id;
+            let (pause, paused) = tokio::sync::watch::channel(false);
+            let (task, handle) = Task::run(
+                runner::run(
+                    procedure.clone(),
+                    workspace.project.current_project_path.clone(),
+                    workspace.project.manifest.default_target.clone(),
+                    paused,
+                ),
+                move |event| Message::ProcedureEvent(id, event),
+            )
+            .abortable();
+            // Replacing an earlier run aborts it
+            workspace.run = Some(ProcedureRun::new(
+                opened.key.clone(),
+                &source,
+                &procedure,
+                pause,
+                handle,
+            ));
+            return task;
+        }
+        Message::SetProcedurePaused(paused) => {
+            if let Some(run) = app
+                .states
+                .tabs
+                .active_mut()
+                .and_then(|workspace| workspace.run.as_mut())
+            {
+                run.set_paused(paused);
+            }
+        }
+        Message::CancelProcedure => {
+            if let Some(run) = app
+                .states
+                .tabs
+                .active_mut()
+                .and_then(|workspace| workspace.run.as_mut())
+            {
+                run.cancel();
+            }
+        }
+        Message::ProcedureEvent(id, event) => {
+            // Events already queued when a run was cancelled are dropped
+            if let Some(run) = app
+                .states
+                .tabs
+                .get_mut(id)
+                .and_then(|workspace| workspace.run.as_mut())
+                && run.is_active()
+            {
+                run.apply(event);
+            }
+        }
+        Message::FilesChanged(id, paths) => {
+            if let Some(workspace) = app.states.tabs.get_mut(id) {
+                workspace.tree.apply_changes(&paths);
+                if let Err(error) = workspace.refresh_opened_file(&paths) {
+                    app.report_error(error, None);
+                }
+            }
+        }
+        Message::FileWatchFailed(id, error) => {
+            // Late failures from a closed tab are not worth reporting
+            if app.states.tabs.get_mut(id).is_some() {
+                app.report_error(error, None);
+            }
+        }
+        Message::OpenTreeMenu(key) => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.tree_cursor = Some(key.clone());
+                workspace.tree_menu = Some(key);
+            }
+        }
+        Message::CloseTreeMenu => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.tree_menu = None;
+            }
+        }
+        Message::StartTreeAction(action) => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.start_tree_action(action);
+                return text_input::focus(views::file_tree::TREE_ACTION_INPUT_ID.clone());
+            }
+        }
+        Message::TreeActionNameChanged(name) => {
+            if let Some(workspace) = app.states.tabs.active_mut()
+                && let Some(pending) = &mut workspace.tree_action
+            {
+                pending.name_input = name;
+                workspace.validate_tree_action();
+            }
+        }
+        Message::ConfirmTreeAction => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                match run_tree_action(workspace) {
+                    Ok(created) => {
+                        let save = schedule_layout_save(workspace);
+                        if let Some(path) = created {
+                            return Task::batch([save, update(app, Message::FileSelected(path))]);
+                        }
+                        return save;
+                    }
+                    Err(error) => app.report_error(error, None),
+                }
+            }
+        }
+        Message::CancelTreeAction => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.tree_action = None;
+            }
+        }
+        Message::TreePressed(key) => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.tree_menu = None;
+                workspace.tree_drag = Some(TreeDrag {
+                    source: key,
+                    over: None,
+                });
+            }
+        }
+        Message::TreeDragOver(key) => {
+            if let Some(workspace) = app.states.tabs.active_mut()
+                && let Some(drag) = &mut workspace.tree_drag
+            {
+                drag.over = (drag.source != key).then_some(key);
+            }
+        }
+        Message::TreeReleased(key) => {
+            if let Some(workspace) = app.states.tabs.active_mut()
+                && let Some(drag) = workspace.tree_drag.take()
+            {
+                // Releasing where the press started is a plain click
+                if drag.source == key {
+                    let entry = workspace
+                        .visible_tree_entries()
+                        .into_iter()
+                        .find(|entry| entry.key == key);
+                    return match entry {
+                        Some(entry) if entry.is_directory => {
+                            update(app, Message::ToggleDirectory(entry.key))
+                        }
+                        Some(entry) => update(app, Message::FileSelected(entry.path)),
+                        None => Task::none(),
+                    };
+                }
+                match move_tree_entry(workspace, &drag.source, &key) {
+                    Ok(()) => return schedule_layout_save(workspace),
+                    Err(error) => app.report_error(error, None),
+                }
+            }
+        }
+        Message::CancelTreeDrag => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.tree_drag = None;
+            }
+        }
+        Message::FilesHovered => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.files_hovering = true;
+            }
+        }
+        Message::FilesHoveredLeft => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.files_hovering = false;
+            }
+        }
+        Message::FileDropped(path) => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.files_hovering = false;
+                let root = workspace.project.current_project_path.clone();
+                let dir = root.join(workspace.creation_dir());
+                match file_ops::copy_into(&path, &dir) {
+                    Ok(copied) => {
+                        workspace.tree.apply_changes(std::slice::from_ref(&copied));
+                        workspace.reveal_tree_entry(&file_tree::entry_key(&root, &copied));
+                        return schedule_layout_save(workspace);
+                    }
+                    Err(error) => app.report_error(error, Some(Message::FileDropped(path))),
+                }
+            }
+        }
+        // File finder messages
+        Message::OpenQuickOpen => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.open_quick_open();
+                return text_input::focus(views::quick_open::QUICK_OPEN_INPUT_ID.clone());
+            }
+        }
+        Message::QuickOpenQueryChanged(query) => {
+            if let Some(workspace) = app.states.tabs.active_mut()
+                && let Some(quick_open) = &mut workspace.quick_open
+            {
+                quick_open.set_query(query, &workspace.recent_files);
+            }
+        }
+        Message::MoveQuickOpenSelection(offset) => {
+            if let Some(workspace) = app.states.tabs.active_mut()
+                && let Some(quick_open) = &mut workspace.quick_open
+            {
+                quick_open.move_selection(&workspace.recent_files, offset);
+            }
+        }
+        Message::SubmitQuickOpen => {
+            let selected = app.states.tabs.active().and_then(|workspace| {
+                let quick_open = workspace.quick_open.as_ref()?;
+                let entry = quick_open.selected_entry(&workspace.recent_files)?;
+                Some(entry.path.clone())
+            });
+            if let Some(path) = selected {
+                return update(app, Message::QuickOpenFile(path));
+            }
+        }
+        Message::QuickOpenFile(path) => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.quick_open = None;
+                let key = file_tree::entry_key(&workspace.project.current_project_path, &path);
+                workspace.reveal_tree_entry(&key);
+                let save = schedule_layout_save(workspace);
+                return Task::batch([save, update(app, Message::FileSelected(path))]);
+            }
+        }
+        Message::CloseQuickOpen => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.quick_open = None;
+            }
+        }
+        // Search messages
+        Message::ShowSearchPanel(show) => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.showing_search = show;
+                if show {
+                    workspace.layout.file_tree_collapsed = false;
+                    return text_input::focus(views::search::SEARCH_INPUT_ID.clone());
+                }
+            }
+        }
+        Message::SearchQueryChanged(query) => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.search.query = query;
+            }
+        }
+        Message::SearchModeSelected(mode) => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.search.mode = mode;
+                return update(app, Message::RunSearch);
+            }
+        }
+        Message::SearchCaseToggled(case_sensitive) => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.search.case_sensitive = case_sensitive;
+                return update(app, Message::RunSearch);
+            }
+        }
+        Message::RunSearch => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                let files = workspace.tree.all_files(&workspace.tree_filter);
+                workspace.search.run(&files);
+            }
+        }
+        Message::SearchResultSelected(path, line) => {
+            let task = update(app, Message::FileSelected(path.clone()));
+            if let Some(workspace) = app.states.tabs.active_mut()
+                && workspace
+                    .opened_file
+                    .as_ref()
+                    .is_some_and(|opened| opened.path == path)
+                && let Some(editor) = &mut workspace.editor
+            {
+                editor.go_to_line(line);
+            }
+            return task;
+        }
+        Message::SearchReplacementChanged(replacement) => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.search.replacement = replacement;
+            }
+        }
+        Message::PreviewReplace => {
+            if let Some(workspace) = app.states.tabs.active_mut()
+                && workspace.search.search.is_some()
+            {
+                workspace.search.previewing_replace = true;
+            }
+        }
+        Message::CancelReplace => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                workspace.search.previewing_replace = false;
+            }
+        }
+        Message::ConfirmReplace => {
+            if let Some(workspace) = app.states.tabs.active_mut()
+                && let Some(search) = workspace.search.search.clone()
+            {
+                let state = &mut workspace.search;
+                match search.replace_in_files(&state.results, &state.replacement) {
+                    Ok(replaced) => state.last_replace = replaced,
+                    Err(error) => {
+                        app.report_error(error, None);
+                        return Task::none();
+                    }
+                }
+                let changed: Vec<_> = state
+                    .last_replace
+                    .iter()
+                    .map(|file| file.path.clone())
+                    .collect();
+                let files = workspace.tree.all_files(&workspace.tree_filter);
+                workspace.search.run(&files);
+                if let Err(error) = workspace.refresh_opened_file(&changed) {
+                    app.report_error(error, None);
+                }
+            }
+        }
+        Message::UndoReplace => {
+            if let Some(workspace) = app.states.tabs.active_mut() {
+                let replaced = std::mem::take(&mut workspace.search.last_replace);
+                match search::restore(&replaced) {
+                    Ok(restored) => {
+                        let files = workspace.tree.all_files(&workspace.tree_filter);
+                        workspace.search.run(&files);
+                        if let Err(error) = workspace.refresh_opened_file(&restored) {
+                            app.report_error(error, None);
+                        }
+                    }
+                    Err(error) => {
+                        // Files already put back no longer match, so a retry skips them
+                        workspace.search.last_replace = replaced;
+                        app.report_error(error, Some(Message::UndoReplace));
+                    }
+                }
+            }
         }
+        // Tab messages
+        Message::SelectTab(id) => {
+            if let Some(index) = app.states.tabs.open.iter().position(|w| w.id == id) {
+                app.states.tabs.active = index;
+                app.view = View::ProjectSelected;
+            }
+        }
+        Message::CycleTabs(offset) => {
+            app.states.tabs.cycle(offset);
+        }
+        Message::CloseTab(id) => {
+            if let Some(mut workspace) = app.states.tabs.close(id)
+                && let Err(error) = persist_layout(&mut workspace)
+            {
+                app.report_error(error, None);
+            }
+            if app.states.tabs.open.is_empty() {
+                app.view = View::NoProjectSelected;
+            }
+        }
+        Message::NewTab => {
+            app.view = View::NoProjectSelected;
+        }
+        Message::ShowWorkspace => {
+            if app.states.tabs.active().is_some() {
+                app.view = View::ProjectSelected;
+            }
+        }
+        // Error banner messages
+        Message::DismissError => {
+            app.states.errors.notices.pop();
+        }
+        Message::RetryAfterError => {
+            if let Some(retry) = app
+                .states
+                .errors
+                .notices
+                .pop()
+                .and_then(|notice| notice.retry)
+            {
+                return update(app, retry);
+            }
+        }
+        Message::ReloadConfig => match config::Config::load(app.conf.paths.clone()) {
+            Ok(conf) => {
+                app.conf = conf;
+                app.load_themes();
+            }
+            Err(error) => app.report_error(error, Some(Message::ReloadConfig)),
+        },
+        // Theme messages
+        Message::ThemeSelected(theme) => {
+            app.conf.theme = theme.to_string();
+            app.states.themes.active = theme;
+            app.save_config();
+        }
+        Message::ReloadThemes => {
+            app.load_themes();
+        }
+    }
+
+    Task::none()
+}
+
+/// Saves the layout once it has stopped changing for [`LAYOUT_SAVE_DELAY`].
+fn schedule_layout_save(workspace: &mut Workspace) -> Task<Message> {
+    workspace.layout.revision += 1;
+    let (id, revision) = (workspace.id, workspace.layout.revision);
+    Task::perform(tokio::time::sleep(LAYOUT_SAVE_DELAY), move |()| {
+        Message::PersistLayout(id, revision)
+    })
+}
+
+/// Writes the workspace's layout into its manifest if it changed since the last save.
+fn persist_layout(workspace: &mut Workspace) -> Result<(), DispatchError> {
+    let project = &mut workspace.project;
+    let layout = workspace.layout.to_settings();
+    if project.manifest.layout == layout {
+        return Ok(());
+    }
+    project.manifest.layout = layout;
+    project.manifest.save(&project.current_project_path)
+}
+
+fn create_new_project(app: &mut Dispatcher) -> Result<(), DispatchError> {
+    let project_path = app
+        .conf
+        .get_data_path()?
+        .join(&app.states.new_project.project_name);
+
+    // `create_dir` rather than `create_dir_all` so a name taken since validation is refused
+    std::fs::create_dir(&project_path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AlreadyExists {
+            DispatchError::Project(format!(
+                "a project named '{}' already exists",
+                app.states.new_project.project_name
+            ))
+        } else {
+            DispatchError::io("creating the project directory", e)
+        }
+    })?;
+    let new_project = &app.states.new_project;
+    if let Err(error) = templates::scaffold(
+        &new_project.template,
+        &project_path,
+        &new_project.project_name,
+        &app.conf.paths.config_dir,
+    ) {
+        // Don't leave a half-scaffolded project holding the name
+        let _ = std::fs::remove_dir_all(&project_path);
+        return Err(error);
+    }
+
+    let project_name = std::mem::take(&mut app.states.new_project).project_name;
+    open_project(app, &project_name)
+}
+
+fn import_project(app: &mut Dispatcher) -> Result<(), DispatchError> {
+    let source = app.cwd.join(app.states.import_project.source.trim());
+    let data_path = app.conf.get_data_path()?;
+    let taken = app.conf.get_taken_project_names()?;
+    let project_name = bundle::import_bundle(&source, &data_path, &taken)?;
+
+    app.states.import_project = ImportProjectState::default();
+    open_project(app, &project_name)
+}
+
+/// Makes `project_name` the current project and records it in the recent list.
+fn open_project(app: &mut Dispatcher, project_name: &str) -> Result<(), DispatchError> {
+    let project_path = app.conf.project_path(project_name)?;
+    if !project_path.is_dir() {
+        return Err(DispatchError::Project(format!(
+            "project '{project_name}' no longer exists"
+        )));
+    }
+
+    // Already open projects just come to the front, keeping their workspace as it was
+    match app.states.tabs.position_of(project_name) {
+        Some(index) => app.states.tabs.active = index,
+        None => {
+            let manifest = ProjectManifest::load_or_create(&project_path, project_name)?;
+            app.states.tabs.open(ProjectState {
+                current_project: project_name.to_string(),
+                current_project_path: project_path,
+                manifest,
+            });
+        }
+    }
+
+    app.conf.record_recent_project(project_name);
+    app.save_config();
+
+    app.view = View::ProjectSelected;
+    Ok(())
+}
+
+fn load_existing_projects(app: &mut Dispatcher, retry: Message) {
+    match app
+        .conf
+        .get_available_projects()
+        .and_then(|projects| Ok((projects, app.conf.get_archived_projects()?)))
+    {
+        Ok((projects, archived)) => {
+            app.states.existing_project.summaries = projects
+                .iter()
+                .map(|name| {
+                    let path = app.conf.project_path(name).unwrap_or_default();
+                    ProjectSummary::read(name, &path)
+                })
+                .collect();
+            app.states.existing_project.available_projects = projects;
+            app.states.project_actions.archived_projects = archived;
+        }
+        Err(error) => app.report_error(error, Some(retry)),
+    }
+}
+
+/// Validates the rename/duplicate input; a rename may keep the selected project's own name.
+fn validate_project_action_name(app: &mut Dispatcher) {
+    let taken = match app.conf.get_taken_project_names() {
+        Ok(taken) => taken,
+        Err(error) => {
+            app.report_error(error, None);
+            return;
+        }
+    };
+    let current = match app.states.project_actions.pending {
+        Some(ProjectAction::Rename) => app.states.existing_project.selected_project.as_deref(),
+        _ => None,
+    };
+    app.states.project_actions.validate_name(&taken, current);
+}
+
+/// Runs the pending rename or duplicate on the selected project.
+fn run_project_action(app: &mut Dispatcher) -> Result<(), DispatchError> {
+    let (Some(action), Some(name)) = (
+        app.states.project_actions.pending,
+        app.states.existing_project.selected_project.clone(),
+    ) else {
+        return Ok(());
+    };
+    let new_name = app.states.project_actions.name_input.clone();
+    let data_path = match action {
+        ProjectAction::Rename => app.conf.data_path_for_move(&name)?,
+        ProjectAction::Duplicate => app.conf.get_data_path()?,
+    };
+
+    match action {
+        ProjectAction::Rename => {
+            project::rename_project(&data_path, &name, &new_name)?;
+            app.conf.rename_recent_project(&name, &new_name);
+            app.save_config();
+            if let Some(index) = app.states.tabs.position_of(&name) {
+                let workspace = &mut app.states.tabs.open[index];
+                workspace.project.current_project = new_name.clone();
+                workspace.project.current_project_path = data_path.join(&new_name);
+                workspace.tree = FileTree::scan(&workspace.project.current_project_path);
+                workspace.refresh_tree_filter();
+                if workspace.project.manifest.name == name {
+                    workspace.project.manifest.name = new_name.clone();
+                }
+            }
+        }
+        ProjectAction::Duplicate => project::duplicate_project(&data_path, &name, &new_name)?,
+    }
+
+    app.states.project_actions.reset_prompt();
+    app.states.existing_project.selected_project = Some(new_name);
+    load_existing_projects(app, Message::LoadExistingProjects);
+    Ok(())
+}
+
+/// Runs the workspace's pending file tree operation, updating the cached tree for just the
+/// paths it touched. Returns a new file that should be opened.
+fn run_tree_action(workspace: &mut Workspace) -> Result<Option<PathBuf>, DispatchError> {
+    let Some(pending) = workspace.tree_action.clone() else {
+        return Ok(None);
+    };
+    if pending.validation_error.is_some() {
+        return Ok(None);
+    }
+    let root = workspace.project.current_project_path.clone();
+    let target = root.join(&pending.target);
+    let name = pending.action.file_name(&pending.name_input);
+
+    let (changed, result) = match pending.action {
+        TreeAction::NewPacket | TreeAction::NewProcedure => {
+            let path = target.join(&name);
+            let contents = match pending.action {
+                TreeAction::NewPacket => file_ops::NEW_PACKET,
+                _ => file_ops::NEW_PROCEDURE,
+            };
+            file_ops::create_file(&path, contents)?;
+            (vec![path.clone()], Some(path))
+        }
+        TreeAction::NewFolder => {
+            let path = target.join(&name);
+            file_ops::create_folder(&path)?;
+            (vec![path.clone()], Some(path))
+        }
+        TreeAction::Rename => {
+            let renamed = file_ops::rename_entry(&target, &name)?;
+            let new_key = file_tree::entry_key(&root, &renamed);
+            workspace.rekey_tree_entries(&pending.target, Some(&new_key))?;
+            (vec![target, renamed.clone()], Some(renamed))
+        }
+        TreeAction::Duplicate => {
+            let copy = file_ops::duplicate_entry(&target, &name)?;
+            (vec![copy.clone()], Some(copy))
+        }
+        TreeAction::Delete => {
+            file_ops::trash_entry(&root, &target)?;
+            workspace.rekey_tree_entries(&pending.target, None)?;
+            (vec![target], None)
+        }
+    };
+    workspace.tree_action = None;
+    workspace.tree.apply_changes(&changed);
+
+    let Some(path) = result else {
+        return Ok(None);
+    };
+    workspace.reveal_tree_entry(&file_tree::entry_key(&root, &path));
+    Ok(pending
+        .action
+        .creates_entry()
+        .then_some(path)
+        .filter(|path| path.is_file()))
+}
+
+/// Moves the entry `source` into the directory `target`, or into the folder holding `target`
+/// when it is a file.
+fn move_tree_entry(
+    workspace: &mut Workspace,
+    source: &str,
+    target: &str,
+) -> Result<(), DispatchError> {
+    let root = workspace.project.current_project_path.clone();
+    let target = root.join(target);
+    let dir = if target.is_dir() {
+        target
+    } else {
+        target.parent().unwrap_or(&root).to_path_buf()
+    };
+    let from = root.join(source);
+    if from.parent() == Some(dir.as_path()) {
+        return Ok(());
+    }
+
+    let moved = file_ops::move_entry(&from, &dir)?;
+    let key = file_tree::entry_key(&root, &moved);
+    workspace.rekey_tree_entries(source, Some(&key))?;
+    workspace.tree.apply_changes(&[from, moved]);
+    workspace.reveal_tree_entry(&key);
+    Ok(())
+}
+
+/// Clears every reference to a project that was deleted or archived.
+fn forget_project(app: &mut Dispatcher, name: &str) {
+    app.states.existing_project.selected_project = None;
+    if let Some(index) = app.states.tabs.position_of(name) {
+        let id = app.states.tabs.open[index].id;
+        app.states.tabs.close(id);
+    }
+    app.prune_recent_projects();
+    load_existing_projects(app, Message::LoadExistingProjects);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_data_dir_flag() {
+        let parse = |args: &[&str]| parse_args(args.iter().map(|arg| arg.to_string()));
+        assert_eq!(parse(&[]).unwrap(), None);
+        assert_eq!(
+            parse(&["--data-dir", "/a"]).unwrap(),
+            Some(PathBuf::from("/a"))
+        );
+        assert_eq!(
+            parse(&["--data-dir=/b"]).unwrap(),
+            Some(PathBuf::from("/b"))
+        );
+        assert!(parse(&["--data-dir"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
     }
 }
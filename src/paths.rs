@@ -0,0 +1,141 @@
+use std::{env, ffi::OsString, path::PathBuf};
+
+use crate::DispatchError;
+
+/// Environment variable that overrides the whole dispatcher root (config and data).
+pub const HOME_OVERRIDE_VAR: &str = "TNET_DISPATCH_HOME";
+
+/// Directories the dispatcher reads from and writes to, resolved at startup.
+#[derive(Debug, Clone, Default)]
+pub struct Paths {
+    /// Directory holding `config.toml`.
+    pub config_dir: PathBuf,
+    /// Directory holding one folder per project.
+    pub data_dir: PathBuf,
+}
+
+impl Paths {
+    /// Resolves the directories in order of precedence:
+    ///
+    /// 1. the `--data-dir` command line flag,
+    /// 2. the `TNET_DISPATCH_HOME` environment variable,
+    /// 3. `XDG_CONFIG_HOME` / `XDG_DATA_HOME`, each falling back to
+    /// 4. `~/.tnet/dispatch`.
+    ///
+    /// An explicit root (1 or 2) keeps `config.toml` at its top level and projects in `DATA/`.
+    pub fn resolve(data_dir_flag: Option<PathBuf>) -> Result<Self, DispatchError> {
+        Self::resolve_with(data_dir_flag, |name| env::var_os(name))
+    }
+
+    /// [`Self::resolve`] with environment variables read through `var`.
+    fn resolve_with(
+        data_dir_flag: Option<PathBuf>,
+        var: impl Fn(&str) -> Option<OsString>,
+    ) -> Result<Self, DispatchError> {
+        let env_path = |name: &str| env_path(&var, name);
+        let xdg_path = |name: &str| env_path(name).filter(|path| path.is_absolute());
+
+        if let Some(root) = data_dir_flag.or_else(|| env_path(HOME_OVERRIDE_VAR)) {
+            return Ok(Self::rooted_at(root));
+        }
+
+        let legacy_root = || -> Result<PathBuf, DispatchError> {
+            let home = env_path("HOME")
+                .or_else(|| env_path("USERPROFILE"))
+                .ok_or_else(|| {
+                    DispatchError::Config(format!(
+                        "could not determine the home directory; set {HOME_OVERRIDE_VAR} or pass --data-dir"
+                    ))
+                })?;
+            Ok(home.join(".tnet").join("dispatch"))
+        };
+
+        let config_dir = match xdg_path("XDG_CONFIG_HOME") {
+            Some(xdg) => xdg.join("tnet").join("dispatch"),
+            None => legacy_root()?,
+        };
+        let data_dir = match xdg_path("XDG_DATA_HOME") {
+            Some(xdg) => xdg.join("tnet").join("dispatch").join("DATA"),
+            None => legacy_root()?.join("DATA"),
+        };
+
+        Ok(Self {
+            config_dir,
+            data_dir,
+        })
+    }
+
+    pub fn rooted_at(root: PathBuf) -> Self {
+        Self {
+            data_dir: root.join("DATA"),
+            config_dir: root,
+        }
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.config_dir.join("config.toml")
+    }
+}
+
+/// Reads an environment variable as a path, ignoring unset or empty values.
+fn env_path(var: impl Fn(&str) -> Option<OsString>, name: &str) -> Option<PathBuf> {
+    var(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(flag: Option<&str>, vars: &[(&str, &str)]) -> Paths {
+        Paths::resolve_with(flag.map(PathBuf::from), |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| OsString::from(value))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn resolves_in_order_of_precedence() {
+        let all = [
+            (HOME_OVERRIDE_VAR, "/override"),
+            ("XDG_CONFIG_HOME", "/xdg-config"),
+            ("XDG_DATA_HOME", "/xdg-data"),
+            ("HOME", "/home/user"),
+        ];
+
+        let flag = resolve(Some("/flag"), &all);
+        assert_eq!(flag.config_dir, PathBuf::from("/flag"));
+        assert_eq!(flag.data_dir, PathBuf::from("/flag/DATA"));
+
+        let home_override = resolve(None, &all);
+        assert_eq!(home_override.config_dir, PathBuf::from("/override"));
+        assert_eq!(home_override.data_dir, PathBuf::from("/override/DATA"));
+
+        let xdg = resolve(None, &all[1..]);
+        assert_eq!(xdg.config_dir, PathBuf::from("/xdg-config/tnet/dispatch"));
+        assert_eq!(xdg.data_dir, PathBuf::from("/xdg-data/tnet/dispatch/DATA"));
+
+        // Empty overrides and relative XDG paths are ignored
+        let legacy = resolve(
+            None,
+            &[
+                (HOME_OVERRIDE_VAR, ""),
+                ("XDG_CONFIG_HOME", "relative"),
+                ("HOME", "/home/user"),
+            ],
+        );
+        assert_eq!(
+            legacy.config_dir,
+            PathBuf::from("/home/user/.tnet/dispatch")
+        );
+        assert_eq!(
+            legacy.data_dir,
+            PathBuf::from("/home/user/.tnet/dispatch/DATA")
+        );
+
+        assert!(Paths::resolve_with(None, |_| None).is_err());
+    }
+}
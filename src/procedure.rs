@@ -0,0 +1,483 @@
+//! The `.proc` procedure format: a versioned list of steps run against a server.
+//!
+//! A procedure is plain text, one step per line. Blank lines and lines starting with `#` are
+//! ignored. The first other line must declare the format version:
+//!
+//! ```text
+//! # Logs in and checks the session.
+//! version 1
+//! connect 127.0.0.1:7000
+//! send packets/login.json
+//! expect status == "ok" within 5s
+//! capture token = session.token
+//! assert ${token} exists
+//! sleep 500ms
+//! disconnect
+//! ```
+//!
+//! Steps in version 1:
+//!
+//! - `connect [host:port]` opens the connection, to the project's default target when no
+//!   address is given.
+//! - `disconnect` closes it.
+//! - `send <path>` sends a packet file, named relative to the project folder.
+//! - `expect <predicate> [within <duration>]` waits for a response matching the predicate,
+//!   by default for [`DEFAULT_EXPECT_TIMEOUT`].
+//! - `capture <name> = <field>` stores a field of the last matched response in `${name}`.
+//! - `sleep <duration>` pauses.
+//! - `assert <predicate>` checks the last matched response or captured values without waiting.
+//!
+//! A predicate is `<subject> == <value>`, `<subject> != <value>`, `<subject> contains <value>`
+//! or `<subject> exists`. The subject is a dotted field path into the response (`session.token`,
+//! `items.0.id`) or a captured variable (`${token}`). Values are JSON literals (`"ok"`, `42`,
+//! `true`, `null`) or variables. Durations are a whole number followed by `ms`, `s` or `m`, up
+//! to 24 hours.
+
+use std::time::Duration;
+
+use thiserror::Error;
+
+/// The newest format version this build understands.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// How long `expect` waits when the step gives no `within`.
+pub const DEFAULT_EXPECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The longest `sleep` or `within`, which keeps deadlines from overflowing.
+pub const MAX_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Procedure {
+    pub version: u32,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    /// One-based line of the step in the file.
+    pub line: usize,
+    pub kind: StepKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StepKind {
+    Connect {
+        /// `host:port`, or the project's default target when `None`.
+        target: Option<String>,
+    },
+    Disconnect,
+    Send {
+        /// Packet file relative to the project folder, `/`-separated.
+        packet: String,
+    },
+    Expect {
+        predicate: Predicate,
+        within: Duration,
+    },
+    Capture {
+        variable: String,
+        field: FieldPath,
+    },
+    Sleep(Duration),
+    Assert(Predicate),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Predicate {
+    pub subject: Subject,
+    pub condition: Condition,
+}
+
+/// What a predicate looks at.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Subject {
+    Field(FieldPath),
+    Variable(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    Equals(Operand),
+    NotEquals(Operand),
+    /// Substring of a string, or element of an array.
+    Contains(Operand),
+    Exists,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operand {
+    Literal(serde_json::Value),
+    Variable(String),
+}
+
+/// A dotted path into a JSON value; numeric segments also index arrays.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldPath(pub Vec<String>);
+
+impl FieldPath {
+    /// The value at this path inside `value`, if there is one.
+    pub fn lookup<'a>(&self, value: &'a serde_json::Value) -> Option<&'a serde_json::Value> {
+        self.0.iter().try_fold(value, |value, segment| match value {
+            serde_json::Value::Object(object) => object.get(segment),
+            serde_json::Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+    }
+}
+
+impl std::fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.join("."))
+    }
+}
+
+/// Where and why a procedure failed to parse; line and column are one-based.
+#[derive(Clone, Debug, PartialEq, Error)]
+#[error("line {line}, column {column}: {message}")]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    /// The error prefixed with the file it came from, as `file:line:column: message`.
+    pub fn in_file(&self, file: &str) -> String {
+        format!("{file}:{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+pub fn parse(source: &str) -> Result<Procedure, ParseError> {
+    let mut version = None;
+    let mut steps = Vec::new();
+
+    for (index, text) in source.lines().enumerate() {
+        let mut line = Line::new(index + 1, text);
+        let Some(keyword) = line.word() else {
+            continue;
+        };
+        if keyword.text.starts_with('#') {
+            continue;
+        }
+
+        if version.is_none() {
+            if keyword.text != "version" {
+                return Err(keyword.error("expected `version 1` before the first step"));
+            }
+            let number = line.expect_word("a version number")?;
+            let parsed = number
+                .text
+                .parse::<u32>()
+                .map_err(|_| number.error("version must be a number"))?;
+            if parsed == 0 || parsed > FORMAT_VERSION {
+                return Err(number.error(&format!(
+                    "unsupported version {parsed}; this build reads version {FORMAT_VERSION}"
+                )));
+            }
+            line.finish()?;
+            version = Some(parsed);
+            continue;
+        }
+
+        let kind = match keyword.text {
+            "connect" => StepKind::Connect {
+                target: line.word().map(|word| word.text.to_string()),
+            },
+            "disconnect" => StepKind::Disconnect,
+            "send" => StepKind::Send {
+                packet: line.expect_word("a packet file")?.text.to_string(),
+            },
+            "expect" => {
+                let predicate = line.predicate()?;
+                let within = match line.word() {
+                    Some(word) if word.text == "within" => line.duration()?,
+                    Some(word) => return Err(word.error("expected `within <duration>`")),
+                    None => DEFAULT_EXPECT_TIMEOUT,
+                };
+                StepKind::Expect { predicate, within }
+            }
+            "capture" => {
+                let variable = line.expect_word("a variable name")?;
+                if !is_identifier(variable.text) {
+                    return Err(variable.error("variable names use letters, digits, `_` and `-`"));
+                }
+                let equals = line.expect_word("`=`")?;
+                if equals.text != "=" {
+                    return Err(equals.error("expected `=`"));
+                }
+                let field = line.expect_word("a field path")?;
+                StepKind::Capture {
+                    variable: variable.text.to_string(),
+                    field: field_path(&field)?,
+                }
+            }
+            "sleep" => StepKind::Sleep(line.duration()?),
+            "assert" => StepKind::Assert(line.predicate()?),
+            "version" => return Err(keyword.error("the version is already declared")),
+            other => return Err(keyword.error(&format!("unknown step `{other}`"))),
+        };
+        line.finish()?;
+        steps.push(Step {
+            line: index + 1,
+            kind,
+        });
+    }
+
+    match version {
+        Some(version) => Ok(Procedure { version, steps }),
+        None => Err(ParseError {
+            line: 1,
+            column: 1,
+            message: "empty procedure; start with `version 1`".to_string(),
+        }),
+    }
+}
+
+/// A word of a line and where it starts.
+struct Word<'a> {
+    text: &'a str,
+    line: usize,
+    /// Zero-based byte offset in the line.
+    offset: usize,
+    source: &'a str,
+}
+
+impl Word<'_> {
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.source[..self.offset].chars().count() + 1,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Splits a line into words; a double-quoted string is one word.
+struct Line<'a> {
+    number: usize,
+    source: &'a str,
+    position: usize,
+}
+
+impl<'a> Line<'a> {
+    fn new(number: usize, source: &'a str) -> Self {
+        Self {
+            number,
+            source,
+            position: 0,
+        }
+    }
+
+    fn word(&mut self) -> Option<Word<'a>> {
+        let rest = &self.source[self.position..];
+        let start = self.position + (rest.len() - rest.trim_start().len());
+        let rest = &self.source[start..];
+        if rest.is_empty() {
+            self.position = start;
+            return None;
+        }
+
+        let len = if rest.starts_with('"') {
+            // Up to the closing quote, skipping escaped ones
+            let mut escaped = false;
+            rest.char_indices()
+                .skip(1)
+                .find(|&(_, c)| {
+                    let closes = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    closes
+                })
+                .map_or(rest.len(), |(index, _)| index + 1)
+        } else {
+            rest.find(char::is_whitespace).unwrap_or(rest.len())
+        };
+        self.position = start + len;
+        Some(Word {
+            text: &rest[..len],
+            line: self.number,
+            offset: start,
+            source: self.source,
+        })
+    }
+
+    fn expect_word(&mut self, what: &str) -> Result<Word<'a>, ParseError> {
+        self.word()
+            .ok_or_else(|| self.error_at_end(&format!("expected {what}")))
+    }
+
+    /// Fails if anything is left on the line.
+    fn finish(&mut self) -> Result<(), ParseError> {
+        match self.word() {
+            Some(word) => Err(word.error(&format!("unexpected `{}`", word.text))),
+            None => Ok(()),
+        }
+    }
+
+    fn error_at_end(&self, message: &str) -> ParseError {
+        ParseError {
+            line: self.number,
+            column: self.source.chars().count() + 1,
+            message: message.to_string(),
+        }
+    }
+
+    fn duration(&mut self) -> Result<Duration, ParseError> {
+        let word = self.expect_word("a duration such as `2s` or `500ms`")?;
+        let split = word
+            .text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(word.text.len());
+        let (amount, unit) = word.text.split_at(split);
+        let amount = amount
+            .parse::<u64>()
+            .map_err(|_| word.error("durations start with a whole number"))?;
+        let duration = match unit {
+            "ms" => Some(Duration::from_millis(amount)),
+            "s" => Some(Duration::from_secs(amount)),
+            "m" => amount.checked_mul(60).map(Duration::from_secs),
+            _ => return Err(word.error("durations end in `ms`, `s` or `m`")),
+        };
+        duration
+            .filter(|duration| *duration <= MAX_DURATION)
+            .ok_or_else(|| word.error("duration too large; the limit is 24 hours"))
+    }
+
+    fn predicate(&mut self) -> Result<Predicate, ParseError> {
+        let subject = self.expect_word("a field or `${variable}`")?;
+        let subject = match variable(subject.text) {
+            Some(name) => Subject::Variable(name.to_string()),
+            None => Subject::Field(field_path(&subject)?),
+        };
+        let operator = self.expect_word("`==`, `!=`, `contains` or `exists`")?;
+        let condition = match operator.text {
+            "exists" => Condition::Exists,
+            "==" => Condition::Equals(self.operand()?),
+            "!=" => Condition::NotEquals(self.operand()?),
+            "contains" => Condition::Contains(self.operand()?),
+            _ => return Err(operator.error("expected `==`, `!=`, `contains` or `exists`")),
+        };
+        Ok(Predicate { subject, condition })
+    }
+
+    fn operand(&mut self) -> Result<Operand, ParseError> {
+        let word = self.expect_word("a value")?;
+        if let Some(name) = variable(word.text) {
+            return Ok(Operand::Variable(name.to_string()));
+        }
+        serde_json::from_str(word.text)
+            .map(Operand::Literal)
+            .map_err(|_| word.error("values are JSON such as \"text\", 42, true or null"))
+    }
+}
+
+/// The name inside `${name}`.
+fn variable(word: &str) -> Option<&str> {
+    word.strip_prefix("${")?
+        .strip_suffix('}')
+        .filter(|name| is_identifier(name))
+}
+
+fn field_path(word: &Word) -> Result<FieldPath, ParseError> {
+    let segments: Vec<String> = word.text.split('.').map(str::to_string).collect();
+    if segments.iter().all(|segment| is_identifier(segment)) {
+        Ok(FieldPath(segments))
+    } else {
+        Err(word.error("field paths look like `session.token` or `items.0.id`"))
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_step() {
+        let procedure = parse(
+            "# comment\nversion 1\n\nconnect\nconnect 10.0.0.1:9\nsend packets/login.json\n\
+             expect status == \"ok\" within 2s\nexpect ${token} exists\ncapture token = session.token\n\
+             sleep 250ms\nassert items.0.id != ${id}\ndisconnect\n",
+        )
+        .unwrap();
+        let kinds: Vec<_> = procedure.steps.iter().map(|step| &step.kind).collect();
+        assert_eq!(procedure.version, 1);
+        assert_eq!(procedure.steps[0].line, 4);
+        assert_eq!(
+            kinds,
+            [
+                &StepKind::Connect { target: None },
+                &StepKind::Connect {
+                    target: Some("10.0.0.1:9".to_string())
+                },
+                &StepKind::Send {
+                    packet: "packets/login.json".to_string()
+                },
+                &StepKind::Expect {
+                    predicate: Predicate {
+                        subject: Subject::Field(FieldPath(vec!["status".to_string()])),
+                        condition: Condition::Equals(Operand::Literal("ok".into())),
+                    },
+                    within: Duration::from_secs(2),
+                },
+                &StepKind::Expect {
+                    predicate: Predicate {
+                        subject: Subject::Variable("token".to_string()),
+                        condition: Condition::Exists,
+                    },
+                    within: DEFAULT_EXPECT_TIMEOUT,
+                },
+                &StepKind::Capture {
+                    variable: "token".to_string(),
+                    field: FieldPath(vec!["session".to_string(), "token".to_string()]),
+                },
+                &StepKind::Sleep(Duration::from_millis(250)),
+                &StepKind::Assert(Predicate {
+                    subject: Subject::Field(FieldPath(vec![
+                        "items".to_string(),
+                        "0".to_string(),
+                        "id".to_string()
+                    ])),
+                    condition: Condition::NotEquals(Operand::Variable("id".to_string())),
+                }),
+                &StepKind::Disconnect,
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_word() {
+        let error = |source: &str| {
+            let error = parse(source).unwrap_err();
+            (error.line, error.column)
+        };
+        assert_eq!(error("connect\n"), (1, 1));
+        assert_eq!(error("version 2\n"), (1, 9));
+        assert_eq!(error("version 1\n  jump\n"), (2, 3));
+        assert_eq!(error("version 1\nexpect a == \"x\" within 2h\n"), (2, 24));
+        assert_eq!(error("version 1\nsend\n"), (2, 5));
+        assert_eq!(error("version 1\nsleep 999999999999999999m\n"), (2, 7));
+        assert_eq!(error("version 1\nsleep 25h\n"), (2, 7));
+        assert_eq!(
+            parse("version 1\nsleep 1441m\n").unwrap_err().message,
+            "duration too large; the limit is 24 hours"
+        );
+        assert_eq!(
+            parse("version 1\nsleep\n").unwrap_err().in_file("a.proc"),
+            "a.proc:2:6: expected a duration such as `2s` or `500ms`"
+        );
+    }
+
+    #[test]
+    fn field_paths_index_objects_and_arrays() {
+        let value = serde_json::json!({"items": [{"id": 7}]});
+        let path = FieldPath(vec!["items".to_string(), "0".to_string(), "id".to_string()]);
+        assert_eq!(path.lookup(&value), Some(&serde_json::json!(7)));
+        assert_eq!(FieldPath(vec!["missing".to_string()]).lookup(&value), None);
+    }
+}
@@ -0,0 +1,519 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{DispatchError, config::unix_now};
+
+/// File inside every project directory that holds its [`ProjectManifest`].
+pub const MANIFEST_FILE: &str = "project.toml";
+
+/// Folder inside the data directory holding deleted projects until they are restored.
+pub const TRASH_DIR: &str = ".trash";
+
+/// Folder inside the data directory holding archived projects.
+pub const ARCHIVE_DIR: &str = ".archive";
+
+/// Folder inside a project holding procedure run history.
+pub const HISTORY_DIR: &str = ".history";
+
+/// Newest manifest schema this build can read and the one it writes.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Metadata and settings stored in a project's `project.toml`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectManifest {
+    pub schema_version: u32,
+    /// Name shown in the UI; the directory name stays the project's identifier.
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Seconds since the Unix epoch.
+    pub created: u64,
+    /// Seconds since the Unix epoch.
+    pub modified: u64,
+    /// Server address (`host:port`) procedures connect to unless they name one themselves.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_target: Option<String>,
+    /// Extensions (without the dot) shown in the file tree.
+    #[serde(default = "default_file_types")]
+    pub file_types: Vec<String>,
+    /// Shows files of every type in the tree, not just `file_types`.
+    #[serde(default)]
+    pub show_all_files: bool,
+    /// `.gitignore`-style patterns for entries hidden from the tree whatever their type.
+    #[serde(default)]
+    pub ignore_patterns: Vec<String>,
+    #[serde(default)]
+    pub layout: LayoutSettings,
+}
+
+/// Panel layout saved per project and restored when it is reopened.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct LayoutSettings {
+    pub horizontal_ratio: f32,
+    pub vertical_ratio: f32,
+    pub file_tree_collapsed: bool,
+    pub inspector_collapsed: bool,
+    /// Directories open in the file tree, relative to the project root with `/` separators.
+    pub expanded_dirs: BTreeSet<String>,
+}
+
+impl Default for LayoutSettings {
+    fn default() -> Self {
+        Self {
+            horizontal_ratio: 0.8,
+            vertical_ratio: 0.7,
+            file_tree_collapsed: false,
+            inspector_collapsed: false,
+            expanded_dirs: BTreeSet::new(),
+        }
+    }
+}
+
+fn default_file_types() -> Vec<String> {
+    vec!["json".to_string(), "proc".to_string()]
+}
+
+impl ProjectManifest {
+    pub fn new(name: &str) -> Self {
+        let now = unix_now();
+        Self {
+            schema_version: MANIFEST_VERSION,
+            name: name.to_string(),
+            description: String::new(),
+            tags: Vec::new(),
+            created: now,
+            modified: now,
+            default_target: None,
+            file_types: default_file_types(),
+            show_all_files: false,
+            ignore_patterns: Vec::new(),
+            layout: LayoutSettings::default(),
+        }
+    }
+
+    pub fn load(project_dir: &Path) -> Result<Self, DispatchError> {
+        let contents = std::fs::read_to_string(project_dir.join(MANIFEST_FILE))
+            .map_err(|e| DispatchError::io("reading the project manifest", e))?;
+        let manifest: Self = toml::from_str(&contents)?;
+        if manifest.schema_version > MANIFEST_VERSION {
+            return Err(DispatchError::Project(format!(
+                "{MANIFEST_FILE} is schema version {}, but this build only understands up to version {MANIFEST_VERSION}",
+                manifest.schema_version
+            )));
+        }
+        Ok(manifest)
+    }
+
+    /// Loads the manifest, writing a fresh one for projects created before manifests existed.
+    pub fn load_or_create(project_dir: &Path, name: &str) -> Result<Self, DispatchError> {
+        if project_dir.join(MANIFEST_FILE).exists() {
+            return Self::load(project_dir);
+        }
+        let manifest = Self::new(name);
+        manifest.save(project_dir)?;
+        Ok(manifest)
+    }
+
+    pub fn save(&self, project_dir: &Path) -> Result<(), DispatchError> {
+        let contents = toml::to_string(self)?;
+        std::fs::write(project_dir.join(MANIFEST_FILE), contents)
+            .map_err(|e| DispatchError::io("writing the project manifest", e))
+    }
+
+    /// Updates the modified timestamp; call before saving user edits.
+    pub fn touch(&mut self) {
+        self.modified = unix_now();
+    }
+}
+
+impl Default for ProjectManifest {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+/// What the project picker shows about a project besides its name.
+#[derive(Clone, Debug)]
+pub struct ProjectSummary {
+    pub name: String,
+    pub description: String,
+    /// Seconds since the Unix epoch, from the manifest or else the folder itself.
+    pub modified: Option<u64>,
+}
+
+impl ProjectSummary {
+    /// Reads the summary without creating a manifest; unreadable manifests leave the
+    /// description empty.
+    pub fn read(name: &str, project_dir: &Path) -> Self {
+        let manifest = ProjectManifest::load(project_dir).ok();
+        let modified = manifest
+            .as_ref()
+            .map(|manifest| manifest.modified)
+            .or_else(|| {
+                std::fs::metadata(project_dir)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|age| age.as_secs())
+            });
+        Self {
+            name: name.to_string(),
+            description: manifest
+                .map(|manifest| manifest.description)
+                .unwrap_or_default(),
+            modified,
+        }
+    }
+}
+
+/// Longest project name accepted, keeping paths well inside filesystem limits.
+pub const MAX_PROJECT_NAME_LEN: usize = 64;
+
+/// Names Windows refuses as file names; rejected everywhere so data folders stay portable.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Checks that `name` can be used as a project directory name next to `existing` projects.
+///
+/// Collisions are detected case-insensitively so names stay distinct on case-insensitive
+/// filesystems and shared folders.
+pub fn validate_project_name(name: &str, existing: &[String]) -> Result<(), String> {
+    if name.contains(' ') {
+        Err("Project name cannot contain spaces".to_string())
+    } else if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    {
+        Err("Project name can only contain letters, numbers, underscores and hyphens".to_string())
+    } else if name.is_empty() {
+        Err("Project name cannot be empty".to_string())
+    } else if name.chars().count() > MAX_PROJECT_NAME_LEN {
+        Err(format!(
+            "Project name cannot be longer than {MAX_PROJECT_NAME_LEN} characters"
+        ))
+    } else if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(name))
+    {
+        Err(format!("'{name}' is a reserved name"))
+    } else if let Some(existing) = find_colliding_project(name, existing) {
+        Err(format!("A project named '{existing}' already exists"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the existing project whose name matches `name` ignoring case.
+pub fn find_colliding_project<'a>(name: &str, existing: &'a [String]) -> Option<&'a String> {
+    let name = name.to_lowercase();
+    existing
+        .iter()
+        .find(|existing| existing.to_lowercase() == name)
+}
+
+/// A project moved to the trash, remembered so the deletion can be undone.
+#[derive(Clone, Debug)]
+pub struct TrashedProject {
+    pub name: String,
+    pub trash_path: PathBuf,
+}
+
+/// Renames the project directory, keeping the display name in sync if it matched the old name.
+pub fn rename_project(
+    data_dir: &Path,
+    old_name: &str,
+    new_name: &str,
+) -> Result<(), DispatchError> {
+    let new_path = vacant_project_path(data_dir, new_name)?;
+    std::fs::rename(data_dir.join(old_name), &new_path)
+        .map_err(|e| DispatchError::io("renaming the project", e))?;
+
+    let mut manifest = ProjectManifest::load_or_create(&new_path, new_name)?;
+    if manifest.name == old_name {
+        manifest.name = new_name.to_string();
+        manifest.touch();
+        manifest.save(&new_path)?;
+    }
+    Ok(())
+}
+
+/// Copies a project into a new directory with a fresh manifest.
+pub fn duplicate_project(data_dir: &Path, name: &str, new_name: &str) -> Result<(), DispatchError> {
+    let new_path = vacant_project_path(data_dir, new_name)?;
+    let source = data_dir.join(name);
+    copy_dir_all(&source, &new_path)?;
+
+    let original = ProjectManifest::load_or_create(&source, name)?;
+    let fresh = ProjectManifest::new(new_name);
+    ProjectManifest {
+        name: new_name.to_string(),
+        created: fresh.created,
+        modified: fresh.modified,
+        ..original
+    }
+    .save(&new_path)
+}
+
+/// Moves a project into the trash folder so it can be restored with [`restore_project`].
+pub fn trash_project(data_dir: &Path, name: &str) -> Result<TrashedProject, DispatchError> {
+    let trash = data_dir.join(TRASH_DIR);
+    std::fs::create_dir_all(&trash)
+        .map_err(|e| DispatchError::io("creating the trash folder", e))?;
+    let trash_path = trash.join(format!("{name}-{}", unix_now()));
+    std::fs::rename(data_dir.join(name), &trash_path)
+        .map_err(|e| DispatchError::io("moving the project to the trash", e))?;
+    Ok(TrashedProject {
+        name: name.to_string(),
+        trash_path,
+    })
+}
+
+pub fn restore_project(data_dir: &Path, trashed: &TrashedProject) -> Result<(), DispatchError> {
+    let path = vacant_project_path(data_dir, &trashed.name)?;
+    std::fs::rename(&trashed.trash_path, path)
+        .map_err(|e| DispatchError::io("restoring the project from the trash", e))
+}
+
+/// Moves a project out of the project list without deleting it.
+///
+/// If the archive already holds a project of that name, this one is stored as `name-2`, `name-3`
+/// and so on rather than replacing it.
+pub fn archive_project(data_dir: &Path, name: &str) -> Result<(), DispatchError> {
+    let archive = data_dir.join(ARCHIVE_DIR);
+    std::fs::create_dir_all(&archive)
+        .map_err(|e| DispatchError::io("creating the archive folder", e))?;
+    let archived_path = (1..)
+        .map(|n| match n {
+            1 => name.to_string(),
+            n => format!("{name}-{n}"),
+        })
+        .find(|candidate| !name_taken(&archive, candidate))
+        .map(|candidate| archive.join(candidate))
+        .expect("the candidate names are unbounded");
+    std::fs::rename(data_dir.join(name), archived_path)
+        .map_err(|e| DispatchError::io("archiving the project", e))
+}
+
+pub fn unarchive_project(data_dir: &Path, name: &str) -> Result<(), DispatchError> {
+    let path = vacant_project_path(data_dir, name)?;
+    std::fs::rename(data_dir.join(ARCHIVE_DIR).join(name), path)
+        .map_err(|e| DispatchError::io("unarchiving the project", e))
+}
+
+/// Returns where a project called `name` would live, failing if that is already taken.
+///
+/// Like [`validate_project_name`], existing names are compared ignoring case.
+pub fn vacant_project_path(data_dir: &Path, name: &str) -> Result<PathBuf, DispatchError> {
+    if name_taken(data_dir, name) {
+        return Err(DispatchError::Project(format!(
+            "a project named '{name}' already exists"
+        )));
+    }
+    Ok(data_dir.join(name))
+}
+
+/// Whether `dir` already holds an entry called `name`, ignoring case.
+fn name_taken(dir: &Path, name: &str) -> bool {
+    let name = name.to_lowercase();
+    std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries
+            .flatten()
+            .any(|entry| entry.file_name().to_string_lossy().to_lowercase() == name)
+    })
+}
+
+/// Recursively copies the directory `from` to `to`, which must not exist yet.
+pub fn copy_dir_all(from: &Path, to: &Path) -> Result<(), DispatchError> {
+    std::fs::create_dir(to)
+        .map_err(|e| DispatchError::io(format!("creating {}", to.display()), e))?;
+    let entries = std::fs::read_dir(from)
+        .map_err(|e| DispatchError::io(format!("reading {}", from.display()), e))?;
+    for entry in entries {
+        let entry =
+            entry.map_err(|e| DispatchError::io(format!("reading {}", from.display()), e))?;
+        let target = to.join(entry.file_name());
+        if entry.path().is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)
+                .map_err(|e| DispatchError::io(format!("copying {}", entry.path().display()), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Files under `dir` whose extension is one of `file_types`, skipping hidden directories.
+pub fn project_files(dir: &Path, file_types: &[String]) -> Result<Vec<PathBuf>, DispatchError> {
+    let mut files = Vec::new();
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| DispatchError::io(format!("reading {}", dir.display()), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if path.is_dir() && !hidden {
+            files.extend(project_files(&path, file_types)?);
+        } else if path.is_file()
+            && path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| file_types.iter().any(|t| t == ext))
+        {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn temp_data_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "tnet-dispatch-project-{}-{name}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes a project with an old manifest and one packet.
+    fn old_project(data_dir: &Path, name: &str) {
+        let dir = data_dir.join(name);
+        std::fs::create_dir(&dir).unwrap();
+        ProjectManifest {
+            created: 1,
+            modified: 1,
+            description: "kept".to_string(),
+            ..ProjectManifest::new(name)
+        }
+        .save(&dir)
+        .unwrap();
+        std::fs::write(dir.join("login.json"), "{}").unwrap();
+    }
+
+    #[test]
+    fn accepts_a_fresh_name() {
+        assert!(validate_project_name("auth-flow_2", &names(&["other"])).is_ok());
+    }
+
+    #[test]
+    fn rejects_collisions_ignoring_case() {
+        let existing = names(&["AuthFlow"]);
+        assert!(validate_project_name("authflow", &existing).is_err());
+        assert_eq!(
+            find_colliding_project("AUTHFLOW", &existing).map(String::as_str),
+            Some("AuthFlow")
+        );
+    }
+
+    #[test]
+    fn rejects_reserved_and_overlong_names() {
+        assert!(validate_project_name("con", &[]).is_err());
+        assert!(validate_project_name("Lpt1", &[]).is_err());
+        assert!(validate_project_name(&"a".repeat(MAX_PROJECT_NAME_LEN), &[]).is_ok());
+        assert!(validate_project_name(&"a".repeat(MAX_PROJECT_NAME_LEN + 1), &[]).is_err());
+    }
+
+    #[test]
+    fn rename_updates_the_manifest_and_refuses_taken_names() {
+        let data_dir = temp_data_dir("rename");
+        old_project(&data_dir, "auth");
+        old_project(&data_dir, "Billing");
+
+        rename_project(&data_dir, "auth", "login").unwrap();
+        assert!(!data_dir.join("auth").exists());
+        let manifest = ProjectManifest::load(&data_dir.join("login")).unwrap();
+        assert_eq!(manifest.name, "login");
+        assert!(manifest.modified > 1);
+
+        assert!(rename_project(&data_dir, "login", "billing").is_err());
+        assert!(data_dir.join("login").exists());
+
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[test]
+    fn duplicate_gets_fresh_timestamps_and_a_vacant_path() {
+        let data_dir = temp_data_dir("duplicate");
+        old_project(&data_dir, "auth");
+
+        duplicate_project(&data_dir, "auth", "auth-copy").unwrap();
+        let copy = ProjectManifest::load(&data_dir.join("auth-copy")).unwrap();
+        assert_eq!(copy.name, "auth-copy");
+        assert_eq!(copy.description, "kept");
+        assert!(copy.created > 1 && copy.modified > 1);
+        assert!(data_dir.join("auth-copy/login.json").exists());
+        assert_eq!(
+            ProjectManifest::load(&data_dir.join("auth"))
+                .unwrap()
+                .created,
+            1
+        );
+
+        assert!(duplicate_project(&data_dir, "auth", "AUTH-copy").is_err());
+
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[test]
+    fn restore_undoes_trash() {
+        let data_dir = temp_data_dir("trash");
+        old_project(&data_dir, "auth");
+        let manifest = std::fs::read_to_string(data_dir.join("auth").join(MANIFEST_FILE)).unwrap();
+
+        let trashed = trash_project(&data_dir, "auth").unwrap();
+        assert!(!data_dir.join("auth").exists());
+        assert!(trashed.trash_path.starts_with(data_dir.join(TRASH_DIR)));
+
+        restore_project(&data_dir, &trashed).unwrap();
+        assert!(!trashed.trash_path.exists());
+        assert_eq!(
+            std::fs::read_to_string(data_dir.join("auth").join(MANIFEST_FILE)).unwrap(),
+            manifest
+        );
+        assert_eq!(
+            std::fs::read_to_string(data_dir.join("auth/login.json")).unwrap(),
+            "{}"
+        );
+
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[test]
+    fn archive_keeps_an_existing_archived_project() {
+        let data_dir = temp_data_dir("archive");
+        old_project(&data_dir, "auth");
+        archive_project(&data_dir, "auth").unwrap();
+        old_project(&data_dir, "auth");
+        std::fs::write(data_dir.join("auth/login.json"), "{\"second\": true}").unwrap();
+
+        archive_project(&data_dir, "auth").unwrap();
+        let archive = data_dir.join(ARCHIVE_DIR);
+        assert!(!data_dir.join("auth").exists());
+        assert_eq!(
+            std::fs::read_to_string(archive.join("auth/login.json")).unwrap(),
+            "{}"
+        );
+        assert_eq!(
+            std::fs::read_to_string(archive.join("auth-2/login.json")).unwrap(),
+            "{\"second\": true}"
+        );
+
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
@@ -0,0 +1,493 @@
+//! Runs a parsed procedure against a server, reporting each step as it goes.
+//!
+//! Packets travel over TCP as newline-delimited JSON: a packet file is sent compacted onto one
+//! line and every line the server writes back is one response. `${name}` in a packet file is
+//! replaced by the captured value before it is sent.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use iced::futures::{SinkExt, Stream};
+use iced::stream;
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::sync::watch;
+
+use crate::procedure::{Condition, Operand, Predicate, Procedure, StepKind, Subject};
+
+/// How long `connect` waits for the server to accept.
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug)]
+pub enum RunEvent {
+    StepStarted(usize),
+    StepFinished {
+        index: usize,
+        elapsed: Duration,
+        outcome: Result<(), String>,
+    },
+    /// Sent once, after the last step or the first failure.
+    Finished,
+}
+
+/// Runs the steps of `procedure` in order, stopping at the first failure.
+///
+/// While `paused` holds `true` the run waits before starting its next step. Dropping the stream
+/// cancels the run and closes the connection.
+pub fn run(
+    procedure: Procedure,
+    project_dir: PathBuf,
+    default_target: Option<String>,
+    mut paused: watch::Receiver<bool>,
+) -> impl Stream<Item = RunEvent> {
+    stream::channel(16, move |mut output| async move {
+        let mut session = Session::new(project_dir, default_target);
+        for (index, step) in procedure.steps.into_iter().enumerate() {
+            if paused.wait_for(|paused| !paused).await.is_err() {
+                break;
+            }
+            let _ = output.send(RunEvent::StepStarted(index)).await;
+            let started = Instant::now();
+            let outcome = session.perform(step.kind).await;
+            let failed = outcome.is_err();
+            let _ = output
+                .send(RunEvent::StepFinished {
+                    index,
+                    elapsed: started.elapsed(),
+                    outcome,
+                })
+                .await;
+            if failed {
+                break;
+            }
+        }
+        let _ = output.send(RunEvent::Finished).await;
+    })
+}
+
+/// What the steps of one run share.
+struct Session {
+    project_dir: PathBuf,
+    default_target: Option<String>,
+    connection: Option<Connection>,
+    /// The response the last `expect` matched, which `capture` and `assert` read.
+    last_response: Option<Value>,
+    variables: HashMap<String, Value>,
+}
+
+struct Connection {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl Session {
+    fn new(project_dir: PathBuf, default_target: Option<String>) -> Self {
+        Self {
+            project_dir,
+            default_target,
+            connection: None,
+            last_response: None,
+            variables: HashMap::new(),
+        }
+    }
+
+    async fn perform(&mut self, step: StepKind) -> Result<(), String> {
+        match step {
+            StepKind::Connect { target } => {
+                let target = target
+                    .or_else(|| self.default_target.clone())
+                    .ok_or("no address given and the project has no default target")?;
+                let stream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(&target))
+                    .await
+                    .map_err(|_| format!("connecting to {target} timed out"))?
+                    .map_err(|e| format!("connecting to {target}: {e}"))?;
+                let (reader, writer) = stream.into_split();
+                self.connection = Some(Connection {
+                    lines: BufReader::new(reader).lines(),
+                    writer,
+                });
+                Ok(())
+            }
+            StepKind::Disconnect => {
+                if let Some(mut connection) = self.connection.take() {
+                    let _ = connection.writer.shutdown().await;
+                }
+                Ok(())
+            }
+            StepKind::Send { packet } => {
+                let path = self.packet_path(&packet).await?;
+                let contents = tokio::fs::read_to_string(&path)
+                    .await
+                    .map_err(|e| format!("reading {packet}: {e}"))?;
+                let value: Value = serde_json::from_str(&self.substitute(&contents))
+                    .map_err(|e| format!("{packet} is not valid JSON: {e}"))?;
+                let line = format!("{value}\n");
+                self.connection()?
+                    .writer
+                    .write_all(line.as_bytes())
+                    .await
+                    .map_err(|e| format!("sending {packet}: {e}"))
+            }
+            StepKind::Expect { predicate, within } => {
+                let deadline = tokio::time::Instant::now() + within;
+                loop {
+                    let line =
+                        tokio::time::timeout_at(deadline, self.connection()?.lines.next_line())
+                            .await
+                            .map_err(|_| format!("no matching response within {within:?}"))?
+                            .map_err(|e| format!("reading a response: {e}"))?
+                            .ok_or("the server closed the connection")?;
+                    // Responses that are not JSON or do not match are passed over
+                    let Ok(response) = serde_json::from_str::<Value>(&line) else {
+                        continue;
+                    };
+                    if self.holds(&predicate, Some(&response))? {
+                        self.last_response = Some(response);
+                        return Ok(());
+                    }
+                }
+            }
+            StepKind::Capture { variable, field } => {
+                let response = self
+                    .last_response
+                    .as_ref()
+                    .ok_or("no response has been matched yet")?;
+                let value = field
+                    .lookup(response)
+                    .ok_or_else(|| format!("the last response has no `{field}`"))?;
+                self.variables.insert(variable, value.clone());
+                Ok(())
+            }
+            StepKind::Sleep(duration) => {
+                tokio::time::sleep(duration).await;
+                Ok(())
+            }
+            StepKind::Assert(predicate) => {
+                if self.holds(&predicate, self.last_response.as_ref())? {
+                    return Ok(());
+                }
+                Err(
+                    match self.resolve(&predicate.subject, self.last_response.as_ref()) {
+                        Some(actual) => format!("assertion failed; the value is {actual}"),
+                        None => "assertion failed; the value is missing".to_string(),
+                    },
+                )
+            }
+        }
+    }
+
+    /// Where `packet` is on disk, refusing paths that lead out of the project folder.
+    async fn packet_path(&self, packet: &str) -> Result<PathBuf, String> {
+        let reading_error = |e: std::io::Error| format!("reading {packet}: {e}");
+        let project_dir = tokio::fs::canonicalize(&self.project_dir)
+            .await
+            .map_err(reading_error)?;
+        let path = tokio::fs::canonicalize(self.project_dir.join(packet))
+            .await
+            .map_err(reading_error)?;
+        if path.starts_with(&project_dir) {
+            Ok(path)
+        } else {
+            Err(format!("{packet} is outside the project folder"))
+        }
+    }
+
+    fn connection(&mut self) -> Result<&mut Connection, String> {
+        self.connection
+            .as_mut()
+            .ok_or_else(|| "not connected; add a `connect` step first".to_string())
+    }
+
+    /// Whether `predicate` is true of `response` and the captured variables.
+    fn holds(&self, predicate: &Predicate, response: Option<&Value>) -> Result<bool, String> {
+        let actual = self.resolve(&predicate.subject, response);
+        let expected = |operand: &Operand| match operand {
+            Operand::Literal(value) => Ok(value.clone()),
+            Operand::Variable(name) => self
+                .variables
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("`${{{name}}}` has not been captured")),
+        };
+        Ok(match &predicate.condition {
+            Condition::Exists => actual.is_some(),
+            Condition::Equals(operand) => actual == Some(&expected(operand)?),
+            Condition::NotEquals(operand) => actual != Some(&expected(operand)?),
+            Condition::Contains(operand) => {
+                let expected = expected(operand)?;
+                match actual {
+                    Some(Value::String(text)) => {
+                        expected.as_str().is_some_and(|part| text.contains(part))
+                    }
+                    Some(Value::Array(items)) => items.contains(&expected),
+                    Some(Value::Object(object)) => expected
+                        .as_str()
+                        .is_some_and(|key| object.contains_key(key)),
+                    _ => false,
+                }
+            }
+        })
+    }
+
+    fn resolve<'a>(&'a self, subject: &Subject, response: Option<&'a Value>) -> Option<&'a Value> {
+        match subject {
+            Subject::Field(field) => field.lookup(response?),
+            Subject::Variable(name) => self.variables.get(name),
+        }
+    }
+
+    /// `text` with every `${name}` of a captured variable replaced by its value.
+    fn substitute(&self, text: &str) -> String {
+        self.variables
+            .iter()
+            .fold(text.to_string(), |text, (name, value)| {
+                let replacement = match value {
+                    // Packets quote their placeholders, so strings go in escaped but unquoted
+                    Value::String(_) => {
+                        let quoted = value.to_string();
+                        quoted[1..quoted.len() - 1].to_string()
+                    }
+                    other => other.to_string(),
+                };
+                text.replace(&format!("${{{name}}}"), &replacement)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use iced::futures::StreamExt;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::procedure;
+
+    /// A project folder holding `login.json` and `whoami.json`, unique to `name`.
+    fn project(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "tnet-dispatch-runner-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("packets")).unwrap();
+        std::fs::write(dir.join("packets/login.json"), r#"{"type": "login"}"#).unwrap();
+        std::fs::write(
+            dir.join("packets/whoami.json"),
+            r#"{"type": "whoami", "token": "${token}"}"#,
+        )
+        .unwrap();
+        dir
+    }
+
+    fn start(
+        source: &str,
+        dir: &Path,
+        target: String,
+        paused: bool,
+    ) -> (impl Stream<Item = RunEvent>, watch::Sender<bool>) {
+        let (pause, paused) = watch::channel(paused);
+        let procedure = procedure::parse(source).unwrap();
+        (
+            run(procedure, dir.to_path_buf(), Some(target), paused),
+            pause,
+        )
+    }
+
+    /// Events reduced to `(step, passed)` for finished steps and `(step, None)` for started ones.
+    fn summary(events: &[RunEvent]) -> Vec<(Option<usize>, Option<bool>)> {
+        events
+            .iter()
+            .map(|event| match event {
+                RunEvent::StepStarted(index) => (Some(*index), None),
+                RunEvent::StepFinished { index, outcome, .. } => {
+                    (Some(*index), Some(outcome.is_ok()))
+                }
+                RunEvent::Finished => (None, None),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn runs_steps_against_a_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = socket.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            let mut received = Vec::new();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                let packet: Value = serde_json::from_str(&line).unwrap();
+                let reply = match packet["type"].as_str() {
+                    Some("login") => r#"{"status": "ok", "session": {"token": "t-1"}}"#,
+                    _ => r#"{"user": "test-user"}"#,
+                };
+                // Lines that are not JSON are skipped by `expect`
+                writer
+                    .write_all(format!("hello\n{reply}\n").as_bytes())
+                    .await
+                    .unwrap();
+                received.push(packet);
+            }
+            received
+        });
+
+        let dir = project("pass");
+        let (events, _pause) = start(
+            "version 1\nconnect\nsend packets/login.json\nexpect status == \"ok\" within 2s\n\
+             capture token = session.token\nsend packets/whoami.json\n\
+             expect user == \"test-user\" within 2s\ndisconnect\n",
+            &dir,
+            target,
+            false,
+        );
+        let events: Vec<_> = events.collect().await;
+        let mut expected: Vec<_> = (0..7)
+            .flat_map(|index| [(Some(index), None), (Some(index), Some(true))])
+            .collect();
+        expected.push((None, None));
+        assert_eq!(summary(&events), expected);
+
+        let received = server.await.unwrap();
+        assert_eq!(received[1]["token"], "t-1");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn stops_when_an_expect_times_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move { listener.accept().await.unwrap() });
+
+        let dir = project("timeout");
+        let (events, _pause) = start(
+            "version 1\nconnect\nexpect status exists within 50ms\ndisconnect\n",
+            &dir,
+            target,
+            false,
+        );
+        let events: Vec<_> = events.collect().await;
+        assert_eq!(
+            summary(&events),
+            [
+                (Some(0), None),
+                (Some(0), Some(true)),
+                (Some(1), None),
+                (Some(1), Some(false)),
+                (None, None)
+            ]
+        );
+        let RunEvent::StepFinished {
+            outcome, elapsed, ..
+        } = &events[3]
+        else {
+            unreachable!()
+        };
+        assert!(outcome.as_ref().unwrap_err().contains("within 50ms"));
+        assert!(*elapsed >= Duration::from_millis(50));
+
+        drop(server);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn refuses_packets_outside_the_project() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = listener.local_addr().unwrap().to_string();
+        let _server = tokio::spawn(async move { listener.accept().await.unwrap() });
+
+        let dir = project("outside");
+        std::fs::write(dir.join("../tnet-dispatch-runner-secret.json"), "{}").unwrap();
+        let (events, _pause) = start(
+            "version 1\nconnect\nsend ../tnet-dispatch-runner-secret.json\n",
+            &dir,
+            target,
+            false,
+        );
+        let events: Vec<_> = events.collect().await;
+        let RunEvent::StepFinished { outcome, .. } = &events[3] else {
+            unreachable!()
+        };
+        assert!(
+            outcome
+                .as_ref()
+                .unwrap_err()
+                .contains("outside the project folder")
+        );
+
+        std::fs::remove_file(dir.join("../tnet-dispatch-runner-secret.json")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn pauses_and_closes_the_connection_when_dropped() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = listener.local_addr().unwrap().to_string();
+
+        let dir = project("pause");
+        let (events, pause) = start("version 1\nconnect\nsleep 1m\n", &dir, target, true);
+        let mut events = Box::pin(events);
+
+        // Nothing starts while paused
+        let held = tokio::time::timeout(Duration::from_millis(100), events.next()).await;
+        assert!(held.is_err());
+        pause.send_replace(false);
+        assert!(matches!(
+            events.next().await,
+            Some(RunEvent::StepStarted(0))
+        ));
+
+        let (mut socket, _) = listener.accept().await.unwrap();
+        assert!(matches!(
+            events.next().await,
+            Some(RunEvent::StepFinished { index: 0, .. })
+        ));
+        assert!(matches!(
+            events.next().await,
+            Some(RunEvent::StepStarted(1))
+        ));
+
+        // Dropping the stream mid-sleep cancels the run and closes the socket
+        drop(events);
+        let mut buffer = [0; 1];
+        let read = tokio::time::timeout(Duration::from_secs(2), socket.read(&mut buffer)).await;
+        assert_eq!(read.unwrap().unwrap(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn evaluates_predicates_and_substitutes_captures() {
+        let mut session = Session::new(PathBuf::new(), None);
+        session
+            .variables
+            .insert("token".to_string(), Value::from("a\"b"));
+        assert_eq!(
+            session.substitute(r#"{"token": "${token}", "other": "${other}"}"#),
+            r#"{"token": "a\"b", "other": "${other}"}"#
+        );
+
+        let response = serde_json::json!({"status": "ok", "tags": ["x"], "token": "a\"b"});
+        let holds = |predicate: &str| {
+            let source = format!("version 1\nassert {predicate}\n");
+            let StepKind::Assert(predicate) =
+                procedure::parse(&source).unwrap().steps[0].kind.clone()
+            else {
+                unreachable!()
+            };
+            session.holds(&predicate, Some(&response))
+        };
+        assert_eq!(holds("status == \"ok\""), Ok(true));
+        assert_eq!(holds("status != \"ok\""), Ok(false));
+        assert_eq!(holds("tags contains \"x\""), Ok(true));
+        assert_eq!(holds("token == ${token}"), Ok(true));
+        assert_eq!(holds("missing exists"), Ok(false));
+        assert!(holds("status == ${unset}").is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! Searching and replacing across the files of a project, one line at a time.
+
+use std::path::PathBuf;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::{DispatchError, file_tree::TreeEntry};
+
+/// Stop collecting results after this many matching lines.
+const MAX_MATCHING_LINES: usize = 1000;
+
+/// How the search pattern is read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SearchMode {
+    #[default]
+    Text,
+    Regex,
+    /// `key` or `key=value`, matching a JSON property in `.json` files.
+    Json,
+}
+
+impl SearchMode {
+    pub const ALL: [Self; 3] = [Self::Text, Self::Regex, Self::Json];
+}
+
+impl std::fmt::Display for SearchMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text => write!(f, "Text"),
+            Self::Regex => write!(f, "Regex"),
+            Self::Json => write!(f, "JSON key=value"),
+        }
+    }
+}
+
+/// A compiled search, ready to run over files.
+#[derive(Clone, Debug)]
+pub struct Search {
+    mode: SearchMode,
+    regex: Regex,
+}
+
+impl Search {
+    /// Compiles `pattern`; the error describes what is wrong with it.
+    pub fn new(pattern: &str, mode: SearchMode, case_sensitive: bool) -> Result<Self, String> {
+        if pattern.is_empty() {
+            return Err("Enter something to search for".to_string());
+        }
+        let source = match mode {
+            SearchMode::Text => regex::escape(pattern),
+            SearchMode::Regex => pattern.to_string(),
+            SearchMode::Json => {
+                let (key, value) = match pattern.split_once('=') {
+                    Some((key, value)) => (key.trim(), Some(value.trim())),
+                    None => (pattern.trim(), None),
+                };
+                // The value is a separate group so a replacement rewrites just the value
+                let value = match value {
+                    Some(value) => {
+                        let value = regex::escape(value.trim_matches('"'));
+                        format!(r#"(?:"{value}"|{value}\b)"#)
+                    }
+                    None => r#"(?:"(?:[^"\\]|\\.)*"|[^,}\]\s]+|[\[{])"#.to_string(),
+                };
+                format!(r#"(?P<key>"{}"\s*:\s*){value}"#, regex::escape(key))
+            }
+        };
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map_err(|e| format!("Invalid pattern: {e}"))?;
+        Ok(Self { mode, regex })
+    }
+
+    /// Matching lines in `files`, grouped by file in the order given.
+    pub fn run(&self, files: &[TreeEntry]) -> Vec<FileMatches> {
+        let mut results = Vec::new();
+        let mut budget = MAX_MATCHING_LINES;
+        for file in files {
+            if budget == 0 {
+                break;
+            }
+            if self.mode == SearchMode::Json
+                && file.path.extension().is_none_or(|ext| ext != "json")
+            {
+                continue;
+            }
+            // Binary and unreadable files are simply not searched
+            let Ok(contents) = std::fs::read_to_string(&file.path) else {
+                continue;
+            };
+            let lines: Vec<&str> = contents.lines().collect();
+            let matches: Vec<_> = lines
+                .iter()
+                .enumerate()
+                .filter(|(_, line)| self.regex.is_match(line))
+                .take(budget)
+                .map(|(index, line)| LineMatch {
+                    line: index,
+                    text: line.to_string(),
+                    before: index
+                        .checked_sub(1)
+                        .map(|previous| lines[previous].to_string()),
+                    after: lines.get(index + 1).map(|next| next.to_string()),
+                })
+                .collect();
+            if !matches.is_empty() {
+                budget -= matches.len();
+                results.push(FileMatches {
+                    path: file.path.clone(),
+                    key: file.key.clone(),
+                    matches,
+                });
+            }
+        }
+        results
+    }
+
+    /// `line` with every match replaced. In text mode `replacement` is literal; in regex mode
+    /// it may use `$1`-style groups; in JSON mode it is the new value, written as JSON.
+    pub fn replace_line(&self, line: &str, replacement: &str) -> String {
+        match self.mode {
+            SearchMode::Text => self
+                .regex
+                .replace_all(line, regex::NoExpand(replacement))
+                .into_owned(),
+            SearchMode::Regex => self.regex.replace_all(line, replacement).into_owned(),
+            SearchMode::Json => {
+                let template = format!("${{key}}{}", replacement.replace('$', "$$"));
+                self.regex.replace_all(line, template.as_str()).into_owned()
+            }
+        }
+    }
+
+    /// Rewrites the matching lines of each file in `results`, returning the original contents
+    /// so the change can be undone with [`restore`].
+    pub fn replace_in_files(
+        &self,
+        results: &[FileMatches],
+        replacement: &str,
+    ) -> Result<Vec<ReplacedFile>, DispatchError> {
+        let mut replaced = Vec::new();
+        for file in results {
+            let original = std::fs::read_to_string(&file.path)
+                .map_err(|e| DispatchError::io(format!("reading {}", file.key), e))?;
+            let updated: String = original
+                .split_inclusive('\n')
+                .map(|line| {
+                    let body = line.trim_end_matches(['\r', '\n']);
+                    let ending = &line[body.len()..];
+                    format!("{}{ending}", self.replace_line(body, replacement))
+                })
+                .collect();
+            if updated == original {
+                continue;
+            }
+            std::fs::write(&file.path, &updated)
+                .map_err(|e| DispatchError::io(format!("writing {}", file.key), e))?;
+            replaced.push(ReplacedFile {
+                path: file.path.clone(),
+                original,
+                updated,
+            });
+        }
+        Ok(replaced)
+    }
+}
+
+/// Every matching line of one file.
+#[derive(Clone, Debug)]
+pub struct FileMatches {
+    pub path: PathBuf,
+    /// Tree key of the file, see [`TreeEntry::key`].
+    pub key: String,
+    pub matches: Vec<LineMatch>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LineMatch {
+    /// Zero-based line number.
+    pub line: usize,
+    pub text: String,
+    /// The lines around the match, for context.
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// A file changed by a bulk replace.
+#[derive(Clone, Debug)]
+pub struct ReplacedFile {
+    pub path: PathBuf,
+    pub original: String,
+    /// What the replace wrote, to tell whether the file was edited since.
+    pub updated: String,
+}
+
+/// Puts back the contents of files changed by a replace, skipping files edited since; returns
+/// the paths it restored.
+pub fn restore(replaced: &[ReplacedFile]) -> Result<Vec<PathBuf>, DispatchError> {
+    let mut restored = Vec::new();
+    for file in replaced {
+        let current = std::fs::read_to_string(&file.path).ok();
+        if current.as_deref() != Some(file.updated.as_str()) {
+            continue;
+        }
+        std::fs::write(&file.path, &file.original)
+            .map_err(|e| DispatchError::io(format!("restoring {}", file.path.display()), e))?;
+        restored.push(file.path.clone());
+    }
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_replace_what_they_match() {
+        let text = Search::new("a.b", SearchMode::Text, true).unwrap();
+        assert_eq!(text.replace_line("a.b axb", "$1"), "$1 axb");
+
+        let regex = Search::new(r"(\w+)@", SearchMode::Regex, true).unwrap();
+        assert_eq!(regex.replace_line("user@host", "$1 at "), "user at host");
+
+        let json = Search::new("user=alice", SearchMode::Json, false).unwrap();
+        assert!(json.regex.is_match(r#"  "user": "Alice","#));
+        assert!(!json.regex.is_match(r#"  "name": "alice","#));
+        assert_eq!(
+            json.replace_line(r#"  "user": "alice","#, r#""bob""#),
+            r#"  "user": "bob","#
+        );
+
+        let any_value = Search::new("retries", SearchMode::Json, true).unwrap();
+        assert_eq!(
+            any_value.replace_line(r#"{"retries": 3}"#, "5"),
+            r#"{"retries": 5}"#
+        );
+        assert!(Search::new("(", SearchMode::Regex, true).is_err());
+    }
+
+    #[test]
+    fn replace_can_be_undone() {
+        let dir = std::env::temp_dir().join(format!("tnet-dispatch-search-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.proc");
+        std::fs::write(&path, "connect\r\nsend ping.json\r\n").unwrap();
+        let files = [TreeEntry {
+            path: path.clone(),
+            key: "a.proc".to_string(),
+            depth: 0,
+            is_directory: false,
+        }];
+
+        let search = Search::new("ping", SearchMode::Text, true).unwrap();
+        let results = search.run(&files);
+        assert_eq!(results[0].matches[0].line, 1);
+        assert_eq!(results[0].matches[0].before.as_deref(), Some("connect"));
+
+        let replaced = search.replace_in_files(&results, "pong").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "connect\r\nsend pong.json\r\n"
+        );
+        assert_eq!(restore(&replaced).unwrap(), std::slice::from_ref(&path));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "connect\r\nsend ping.json\r\n"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,21 +1,427 @@
-use std::path::PathBuf;
+use std::{collections::BTreeSet, path::PathBuf, time::Duration};
 
+use iced::{Theme, task};
+use tokio::sync::watch;
 
+use crate::{
+    DispatchError,
+    app::Message,
+    config::RecentProject,
+    editor::{FileEditor, Language},
+    file_info::OpenedFile,
+    file_ops,
+    file_tree::{self, FileTree, TreeEntry, TreeFilter, TreeKey},
+    fuzzy,
+    procedure::Procedure,
+    project::{self, LayoutSettings, ProjectManifest, ProjectSummary, TrashedProject},
+    runner::RunEvent,
+    search::{FileMatches, ReplacedFile, Search, SearchMode},
+    templates::ProjectTemplate,
+    themes,
+};
 
+#[derive(Default)]
 pub struct StateValues {
     pub new_project: NewProjectState,
-    pub project: ProjectState,
-    pub layout: LayoutState,
+    pub tabs: WorkspaceTabs,
     pub existing_project: ExistingProjectState,
+    pub project_actions: ProjectActionsState,
+    pub project_settings: ProjectSettingsState,
+    pub export_project: ExportProjectState,
+    pub import_project: ImportProjectState,
+    pub errors: ErrorState,
+    pub themes: ThemeState,
 }
 
 impl StateValues {
     pub fn new() -> Self {
-        Self {
-            new_project: NewProjectState::default(),
-            project: ProjectState::default(),
-            layout: LayoutState::default(),
-            existing_project: ExistingProjectState::default(),
+        Self::default()
+    }
+}
+
+/// Identifies an open workspace; never reused, so late messages for a closed tab are ignored.
+pub type WorkspaceId = u64;
+
+/// Everything belonging to one open project tab, kept while other tabs are in front.
+pub struct Workspace {
+    pub id: WorkspaceId,
+    pub project: ProjectState,
+    pub layout: LayoutState,
+    /// The project folder's contents, kept current by the file watcher.
+    pub tree: FileTree,
+    /// What the tree shows, built from the manifest; see [`Self::refresh_tree_filter`].
+    pub tree_filter: TreeFilter,
+    /// Key of the file tree entry moved with the arrow keys.
+    pub tree_cursor: Option<String>,
+    /// File shown in the main view and described in the inspector.
+    pub opened_file: Option<OpenedFile>,
+    /// Key of the entry whose context menu is showing.
+    pub tree_menu: Option<String>,
+    /// Tree operation waiting for a name or confirmation.
+    pub tree_action: Option<PendingTreeAction>,
+    /// Entry being dragged to another folder.
+    pub tree_drag: Option<TreeDrag>,
+    /// Set while files from outside the app are dragged over the window.
+    pub files_hovering: bool,
+    /// Keys of files opened in this tab, most recent first.
+    pub recent_files: Vec<String>,
+    /// The Ctrl+P file finder, while it is showing.
+    pub quick_open: Option<QuickOpenState>,
+    /// Shows the search panel in place of the file tree.
+    pub showing_search: bool,
+    pub search: SearchState,
+    /// Buffer of the opened file in the main view, present whenever `opened_file` is.
+    pub editor: Option<FileEditor>,
+    /// The last procedure run in this tab.
+    pub run: Option<ProcedureRun>,
+}
+
+/// An entry dragged within the file tree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeDrag {
+    /// Key of the entry picked up.
+    pub source: String,
+    /// Key of the entry under the mouse, once it has left the source.
+    pub over: Option<String>,
+}
+
+impl Workspace {
+    /// Rows currently shown in the file tree.
+    pub fn visible_tree_entries(&self) -> Vec<TreeEntry> {
+        self.tree
+            .visible_entries(&self.layout.expanded_dirs, &self.tree_filter)
+    }
+
+    /// Rebuilds the tree filter after the manifest or project path changed.
+    pub fn refresh_tree_filter(&mut self) {
+        self.tree_filter =
+            TreeFilter::new(&self.project.current_project_path, &self.project.manifest);
+    }
+
+    /// The file under the tree cursor, if the cursor is on a file.
+    pub fn cursor_file(&self) -> Option<PathBuf> {
+        let cursor = self.tree_cursor.as_ref()?;
+        self.visible_tree_entries()
+            .into_iter()
+            .find(|entry| &entry.key == cursor && !entry.is_directory)
+            .map(|entry| entry.path)
+    }
+
+    /// Shows `opened` in the main view with a fresh editor.
+    pub fn show_file(&mut self, opened: OpenedFile) {
+        let language = Language::of(&opened.path);
+        self.editor = Some(FileEditor::new(&opened.contents, language));
+        self.opened_file = Some(opened);
+    }
+
+    /// Re-reads the opened file if it is among `changed`, closing it if it was removed.
+    ///
+    /// Unsaved edits are kept either way; saving them writes the file back.
+    pub fn refresh_opened_file(&mut self, changed: &[PathBuf]) -> Result<(), DispatchError> {
+        let Some(opened) = &self.opened_file else {
+            return Ok(());
+        };
+        if !changed.contains(&opened.path) {
+            return Ok(());
+        }
+        let dirty = self.editor.as_ref().is_some_and(|editor| editor.dirty);
+        if !opened.path.is_file() {
+            if !dirty {
+                self.opened_file = None;
+                self.editor = None;
+            }
+            return Ok(());
+        }
+        let opened = OpenedFile::read(&opened.path, opened.key.clone())?;
+        if let Some(editor) = &mut self.editor {
+            editor.reload(&opened.contents);
+        }
+        self.opened_file = Some(opened);
+        Ok(())
+    }
+
+    /// Opens or closes a directory in the file tree and puts the cursor on it.
+    pub fn toggle_directory(&mut self, key: &str) {
+        if !self.layout.expanded_dirs.remove(key) {
+            self.layout.expanded_dirs.insert(key.to_string());
+        }
+        self.tree_cursor = Some(key.to_string());
+    }
+
+    /// Applies a key press to the file tree; returns whether a directory opened or closed.
+    pub fn navigate_tree(&mut self, key: TreeKey) -> bool {
+        let entries = self.visible_tree_entries();
+        let current = self
+            .tree_cursor
+            .as_ref()
+            .and_then(|cursor| entries.iter().position(|entry| &entry.key == cursor));
+        let Some(index) = current else {
+            // The first key press just places the cursor
+            self.tree_cursor = entries.first().map(|entry| entry.key.clone());
+            return false;
+        };
+
+        let entry = &entries[index];
+        let expanded = self.layout.expanded_dirs.contains(&entry.key);
+        let target = match key {
+            TreeKey::Up => entries.get(index.saturating_sub(1)),
+            TreeKey::Down => entries.get(index + 1),
+            TreeKey::Right if entry.is_directory && !expanded => {
+                self.layout.expanded_dirs.insert(entry.key.clone());
+                return true;
+            }
+            // Step into an open directory if it has children
+            TreeKey::Right => entries
+                .get(index + 1)
+                .filter(|next| expanded && next.depth > entry.depth),
+            TreeKey::Left if entry.is_directory && expanded => {
+                self.layout.expanded_dirs.remove(&entry.key);
+                return true;
+            }
+            TreeKey::Left => file_tree::parent_key(&entry.key)
+                .and_then(|parent| entries.iter().find(|entry| entry.key == parent)),
+            TreeKey::Enter if entry.is_directory => {
+                let key = entry.key.clone();
+                self.toggle_directory(&key);
+                return true;
+            }
+            TreeKey::Enter => None,
+        };
+        if let Some(target) = target {
+            self.tree_cursor = Some(target.key.clone());
+        }
+        false
+    }
+
+    /// Records `key` as the most recently opened file.
+    pub fn remember_recent_file(&mut self, key: &str) {
+        self.recent_files.retain(|recent| recent != key);
+        self.recent_files.insert(0, key.to_string());
+        self.recent_files.truncate(RECENT_FILES_LIMIT);
+    }
+
+    /// Shows the file finder over every file the tree filter lets through.
+    pub fn open_quick_open(&mut self) {
+        let mut quick_open = QuickOpenState {
+            files: self.tree.all_files(&self.tree_filter),
+            ..QuickOpenState::default()
+        };
+        quick_open.refresh_preview(&self.recent_files);
+        self.quick_open = Some(quick_open);
+    }
+
+    /// Key of the folder new entries go in: the directory under the cursor, the one holding
+    /// the file under it, or the project root (`""`).
+    pub fn creation_dir(&self) -> String {
+        let Some(cursor) = &self.tree_cursor else {
+            return String::new();
+        };
+        let is_directory = self
+            .visible_tree_entries()
+            .iter()
+            .any(|entry| &entry.key == cursor && entry.is_directory);
+        if is_directory {
+            cursor.clone()
+        } else {
+            file_tree::parent_key(cursor)
+                .unwrap_or_default()
+                .to_string()
+        }
+    }
+
+    /// Opens the directories above `key` and puts the cursor on it.
+    pub fn reveal_tree_entry(&mut self, key: &str) {
+        let mut parent = file_tree::parent_key(key);
+        while let Some(dir) = parent {
+            self.layout.expanded_dirs.insert(dir.to_string());
+            parent = file_tree::parent_key(dir);
+        }
+        self.tree_cursor = Some(key.to_string());
+    }
+
+    /// Asks for a name or confirmation for `action` on the entry under the cursor; new entries
+    /// go in [`Self::creation_dir`].
+    pub fn start_tree_action(&mut self, action: TreeAction) {
+        let target = match &self.tree_cursor {
+            _ if action.creates_entry() => self.creation_dir(),
+            Some(cursor) => cursor.clone(),
+            None => return,
+        };
+
+        let root = &self.project.current_project_path;
+        let name_input = match action {
+            TreeAction::Rename | TreeAction::Delete => {
+                target.rsplit('/').next().unwrap_or_default().to_string()
+            }
+            TreeAction::Duplicate => {
+                let path = root.join(&target);
+                let name = target.rsplit('/').next().unwrap_or_default();
+                file_ops::copy_name(name, path.parent().unwrap_or(root))
+            }
+            _ => String::new(),
+        };
+        self.tree_menu = None;
+        self.tree_action = Some(PendingTreeAction {
+            action,
+            target,
+            name_input,
+            validation_error: None,
+        });
+        self.validate_tree_action();
+    }
+
+    /// Follows the entry `old` (and everything below it) to `new` after a rename, or forgets it
+    /// after a delete: expanded directories, the cursor and the opened file.
+    pub fn rekey_tree_entries(
+        &mut self,
+        old: &str,
+        new: Option<&str>,
+    ) -> Result<(), DispatchError> {
+        let rekey = |key: &str| -> Option<Option<String>> {
+            let rest = key.strip_prefix(old)?;
+            if !rest.is_empty() && !rest.starts_with('/') {
+                return None;
+            }
+            Some(new.map(|new| format!("{new}{rest}")))
+        };
+
+        let expanded = std::mem::take(&mut self.layout.expanded_dirs);
+        self.layout.expanded_dirs = expanded
+            .into_iter()
+            .filter_map(|key| rekey(&key).unwrap_or(Some(key)))
+            .collect();
+        if let Some(cursor) = self.tree_cursor.as_deref().and_then(rekey) {
+            self.tree_cursor = cursor;
+        }
+        if let Some(opened) = &self.opened_file
+            && let Some(key) = rekey(&opened.key)
+        {
+            // The editor follows the file; its buffer is the same text under a new name
+            self.opened_file = match key {
+                Some(key) => Some(OpenedFile::read(
+                    &self.project.current_project_path.join(&key),
+                    key,
+                )?),
+                None => {
+                    self.editor = None;
+                    None
+                }
+            };
+        }
+        Ok(())
+    }
+
+    /// Checks the pending action's name against the entries already in its directory.
+    pub fn validate_tree_action(&mut self) {
+        let root = self.project.current_project_path.clone();
+        let Some(pending) = &mut self.tree_action else {
+            return;
+        };
+        let target = root.join(&pending.target);
+        pending.validation_error = match pending.action {
+            TreeAction::Delete => None,
+            TreeAction::Rename => file_ops::validate_entry_name(
+                &pending.name_input,
+                target.parent().unwrap_or(&root),
+                Some(&target),
+            )
+            .err(),
+            TreeAction::Duplicate => file_ops::validate_entry_name(
+                &pending.name_input,
+                target.parent().unwrap_or(&root),
+                None,
+            )
+            .err(),
+            action => {
+                file_ops::validate_entry_name(&action.file_name(&pending.name_input), &target, None)
+                    .err()
+            }
+        };
+    }
+}
+
+/// Projects open side by side, shown as tabs above the workspace.
+#[derive(Default)]
+pub struct WorkspaceTabs {
+    pub open: Vec<Workspace>,
+    /// Index into `open` of the tab in front.
+    pub active: usize,
+    next_id: WorkspaceId,
+}
+
+impl WorkspaceTabs {
+    pub fn active(&self) -> Option<&Workspace> {
+        self.open.get(self.active)
+    }
+
+    pub fn active_mut(&mut self) -> Option<&mut Workspace> {
+        self.open.get_mut(self.active)
+    }
+
+    pub fn get_mut(&mut self, id: WorkspaceId) -> Option<&mut Workspace> {
+        self.open.iter_mut().find(|workspace| workspace.id == id)
+    }
+
+    /// The open tab whose layout is still at `revision`, so a save scheduled then is due.
+    ///
+    /// Stale saves are dropped because a newer one follows, and saves for closed tabs were
+    /// flushed when they closed.
+    pub fn due_layout_save(&mut self, id: WorkspaceId, revision: u64) -> Option<&mut Workspace> {
+        self.get_mut(id)
+            .filter(|workspace| workspace.layout.revision == revision)
+    }
+
+    /// Index of the tab showing the project called `name`.
+    pub fn position_of(&self, name: &str) -> Option<usize> {
+        self.open
+            .iter()
+            .position(|workspace| workspace.project.current_project == name)
+    }
+
+    /// Adds a tab for `project` and brings it to the front.
+    pub fn open(&mut self, project: ProjectState) -> &mut Workspace {
+        let layout = LayoutState::from_settings(&project.manifest.layout);
+        let tree = FileTree::scan(&project.current_project_path);
+        let tree_filter = TreeFilter::new(&project.current_project_path, &project.manifest);
+        self.next_id += 1;
+        self.open.push(Workspace {
+            id: self.next_id,
+            project,
+            layout,
+            tree,
+            tree_filter,
+            tree_cursor: None,
+            opened_file: None,
+            tree_menu: None,
+            tree_action: None,
+            tree_drag: None,
+            files_hovering: false,
+            recent_files: Vec::new(),
+            quick_open: None,
+            showing_search: false,
+            search: SearchState::default(),
+            editor: None,
+            run: None,
+        });
+        self.active = self.open.len() - 1;
+        &mut self.open[self.active]
+    }
+
+    /// Removes the tab, keeping the one in front unless it was the one closed.
+    pub fn close(&mut self, id: WorkspaceId) -> Option<Workspace> {
+        let index = self.open.iter().position(|workspace| workspace.id == id)?;
+        let workspace = self.open.remove(index);
+        if index < self.active || self.active >= self.open.len() {
+            self.active = self.active.saturating_sub(1);
+        }
+        Some(workspace)
+    }
+
+    /// Brings the tab `offset` places away to the front, wrapping around at the ends.
+    pub fn cycle(&mut self, offset: isize) {
+        if !self.open.is_empty() {
+            let len = self.open.len() as isize;
+            self.active = (self.active as isize + offset).rem_euclid(len) as usize;
         }
     }
 }
@@ -23,36 +429,753 @@ impl StateValues {
 pub struct LayoutState {
     pub horizontal_ratio: f32, // Ratio between left and right panels (0.8 = 80% left, 20% right)
     pub vertical_ratio: f32,   // Ratio between top and bottom panels (0.7 = 70% top, 30% bottom)
+    pub file_tree_collapsed: bool,
+    pub inspector_collapsed: bool,
+    pub expanded_dirs: BTreeSet<String>,
     pub is_dragging_horizontal: bool,
     pub is_dragging_vertical: bool,
+    /// Bumped on every change; a pending save only runs if no newer change followed it.
+    pub revision: u64,
 }
 
-impl Default for LayoutState {
-    fn default() -> Self {
+impl LayoutState {
+    pub fn from_settings(settings: &LayoutSettings) -> Self {
         Self {
-            horizontal_ratio: 0.8,
-            vertical_ratio: 0.7,
+            horizontal_ratio: settings.horizontal_ratio,
+            vertical_ratio: settings.vertical_ratio,
+            file_tree_collapsed: settings.file_tree_collapsed,
+            inspector_collapsed: settings.inspector_collapsed,
+            expanded_dirs: settings.expanded_dirs.clone(),
             is_dragging_horizontal: false,
             is_dragging_vertical: false,
+            revision: 0,
         }
     }
+
+    pub fn to_settings(&self) -> LayoutSettings {
+        LayoutSettings {
+            horizontal_ratio: self.horizontal_ratio,
+            vertical_ratio: self.vertical_ratio,
+            file_tree_collapsed: self.file_tree_collapsed,
+            inspector_collapsed: self.inspector_collapsed,
+            expanded_dirs: self.expanded_dirs.clone(),
+        }
+    }
+}
+
+impl Default for LayoutState {
+    fn default() -> Self {
+        Self::from_settings(&LayoutSettings::default())
+    }
 }
 
 #[derive(Default)]
 pub struct ProjectState {
     pub current_project: String,
-    pub current_project_path: PathBuf
+    pub current_project_path: PathBuf,
+    pub manifest: ProjectManifest,
 }
 
 #[derive(Default)]
 pub struct NewProjectState {
     pub project_name: String,
     pub validation_error: Option<String>,
+    /// Active and archived project names, loaded when the dialog opens.
+    pub taken_names: Vec<String>,
+    /// Existing active project the typed name collides with, offered as "open instead".
+    pub existing_match: Option<String>,
+    /// Built-in and saved templates, loaded when the dialog opens.
+    pub templates: Vec<ProjectTemplate>,
+    pub template: ProjectTemplate,
 }
 
 #[derive(Default)]
 pub struct ExistingProjectState {
     pub available_projects: Vec<String>,
+    /// Description and modification date of each available project, read when the list loads.
+    pub summaries: Vec<ProjectSummary>,
     pub selected_project: Option<String>,
-    pub is_dropdown_open: bool,
+    /// Text typed into the picker's filter box, fuzzy matched against project names.
+    pub query: String,
+    pub sort: ProjectSort,
+    /// Folder typed into "Open folder", linked as a project when confirmed.
+    pub folder_path: String,
+}
+
+impl ExistingProjectState {
+    /// Projects matching the filter, best match first, ties broken by the chosen sort order.
+    pub fn visible_projects(&self, recent: &[RecentProject]) -> Vec<&ProjectSummary> {
+        let last_opened = |name: &str| {
+            recent
+                .iter()
+                .find(|recent| recent.name == name)
+                .map(|recent| recent.last_opened)
+        };
+
+        let mut visible: Vec<_> = self
+            .summaries
+            .iter()
+            .filter_map(|summary| Some((fuzzy::score(&self.query, &summary.name)?, summary)))
+            .collect();
+        visible.sort_by(|(a_score, a), (b_score, b)| {
+            let by_sort = match self.sort {
+                ProjectSort::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+                ProjectSort::LastOpened => last_opened(&b.name)
+                    .cmp(&last_opened(&a.name))
+                    .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())),
+            };
+            b_score.cmp(a_score).then(by_sort)
+        });
+        visible.into_iter().map(|(_, summary)| summary).collect()
+    }
+
+    /// Moves the selection `offset` rows through the visible projects, stopping at the ends.
+    pub fn move_selection(&mut self, recent: &[RecentProject], offset: isize) {
+        let visible = self.visible_projects(recent);
+        if visible.is_empty() {
+            return;
+        }
+        let current = self
+            .selected_project
+            .as_ref()
+            .and_then(|selected| visible.iter().position(|summary| &summary.name == selected));
+        let next = match current {
+            Some(index) => index.saturating_add_signed(offset).min(visible.len() - 1),
+            None => 0,
+        };
+        self.selected_project = Some(visible[next].name.clone());
+    }
+}
+
+/// Order of the project picker when the filter does not decide it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProjectSort {
+    Name,
+    #[default]
+    LastOpened,
+}
+
+impl ProjectSort {
+    pub const ALL: [Self; 2] = [Self::LastOpened, Self::Name];
+}
+
+impl std::fmt::Display for ProjectSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Name => write!(f, "Name"),
+            Self::LastOpened => write!(f, "Last opened"),
+        }
+    }
+}
+
+/// The project-wide search and replace panel.
+#[derive(Default)]
+pub struct SearchState {
+    pub query: String,
+    pub mode: SearchMode,
+    pub case_sensitive: bool,
+    pub replacement: String,
+    /// The search that produced `results`, reused for replacing.
+    pub search: Option<Search>,
+    pub results: Vec<FileMatches>,
+    pub error: Option<String>,
+    /// Set while the replace preview is showing.
+    pub previewing_replace: bool,
+    /// Files changed by the last replace, kept so it can be undone.
+    pub last_replace: Vec<ReplacedFile>,
+}
+
+impl SearchState {
+    /// Searches `files` with the current query, or records why the query is invalid.
+    pub fn run(&mut self, files: &[TreeEntry]) {
+        self.previewing_replace = false;
+        match Search::new(&self.query, self.mode, self.case_sensitive) {
+            Ok(search) => {
+                self.results = search.run(files);
+                self.search = Some(search);
+                self.error = None;
+            }
+            Err(error) => {
+                self.results.clear();
+                self.search = None;
+                self.error = (!self.query.is_empty()).then_some(error);
+            }
+        }
+    }
+
+    pub fn match_count(&self) -> usize {
+        self.results.iter().map(|file| file.matches.len()).sum()
+    }
+}
+
+/// A procedure run in one tab, shown in the inspector while it goes and after it ends.
+pub struct ProcedureRun {
+    /// Tree key of the procedure file.
+    pub key: String,
+    pub steps: Vec<StepRun>,
+    pub state: RunState,
+    /// Holds the runner before its next step while `true`.
+    paused: watch::Sender<bool>,
+    /// Aborts the runner when the run is cancelled, replaced or its tab closed.
+    handle: task::Handle,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunState {
+    Running,
+    /// Waiting to start the next step; the step already running finishes first.
+    Paused,
+    Passed,
+    Failed,
+    Cancelled,
+}
+
+pub struct StepRun {
+    /// The step as written in the procedure.
+    pub source: String,
+    pub status: StepStatus,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StepStatus {
+    Pending,
+    Running,
+    Passed(Duration),
+    Failed(Duration, String),
+    /// Not reached because an earlier step failed or the run was cancelled.
+    Skipped,
+}
+
+impl ProcedureRun {
+    pub fn new(
+        key: String,
+        source: &str,
+        procedure: &Procedure,
+        paused: watch::Sender<bool>,
+        handle: task::Handle,
+    ) -> Self {
+        let lines: Vec<&str> = source.lines().collect();
+        let steps = procedure
+            .steps
+            .iter()
+            .map(|step| StepRun {
+                source: lines
+                    .get(step.line - 1)
+                    .map_or_else(String::new, |line| line.trim().to_string()),
+                status: StepStatus::Pending,
+            })
+            .collect();
+        Self {
+            key,
+            steps,
+            state: RunState::Running,
+            paused,
+            handle: handle.abort_on_drop(),
+        }
+    }
+
+    /// Whether the run has not finished or been cancelled yet.
+    pub fn is_active(&self) -> bool {
+        matches!(self.state, RunState::Running | RunState::Paused)
+    }
+
+    pub fn apply(&mut self, event: RunEvent) {
+        match event {
+            RunEvent::StepStarted(index) => {
+                if let Some(step) = self.steps.get_mut(index) {
+                    step.status = StepStatus::Running;
+                }
+            }
+            RunEvent::StepFinished {
+                index,
+                elapsed,
+                outcome,
+            } => {
+                if let Some(step) = self.steps.get_mut(index) {
+                    step.status = match outcome {
+                        Ok(()) => StepStatus::Passed(elapsed),
+                        Err(error) => StepStatus::Failed(elapsed, error),
+                    };
+                }
+            }
+            RunEvent::Finished => {
+                let failed = self
+                    .steps
+                    .iter()
+                    .any(|step| matches!(step.status, StepStatus::Failed(..)));
+                self.state = if failed {
+                    RunState::Failed
+                } else {
+                    RunState::Passed
+                };
+                self.skip_pending();
+            }
+        }
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.is_active() {
+            self.paused.send_replace(paused);
+            self.state = if paused {
+                RunState::Paused
+            } else {
+                RunState::Running
+            };
+        }
+    }
+
+    /// Stops the runner, closing its connection.
+    pub fn cancel(&mut self) {
+        if !self.is_active() {
+            return;
+        }
+        self.handle.abort();
+        self.state = RunState::Cancelled;
+        for step in &mut self.steps {
+            if step.status == StepStatus::Running {
+                step.status = StepStatus::Failed(Duration::ZERO, "cancelled".to_string());
+            }
+        }
+        self.skip_pending();
+    }
+
+    fn skip_pending(&mut self) {
+        for step in &mut self.steps {
+            if step.status == StepStatus::Pending {
+                step.status = StepStatus::Skipped;
+            }
+        }
+    }
+}
+
+/// How many recently opened files each tab remembers for the file finder.
+const RECENT_FILES_LIMIT: usize = 10;
+
+/// How many matches the file finder lists.
+const QUICK_OPEN_RESULTS: usize = 50;
+
+/// How many lines of the selected file the file finder previews.
+const QUICK_OPEN_PREVIEW_LINES: usize = 40;
+
+/// The Ctrl+P file finder.
+#[derive(Clone, Debug, Default)]
+pub struct QuickOpenState {
+    pub query: String,
+    /// Files of the project when the finder opened.
+    pub files: Vec<TreeEntry>,
+    /// Index into [`Self::matches`].
+    pub selected: usize,
+    /// First lines of the selected file.
+    pub preview: Option<String>,
+}
+
+impl QuickOpenState {
+    /// Files matching the query, best first; recently opened files rank above equal matches
+    /// and lead the list when the query is empty.
+    pub fn matches(&self, recent: &[String]) -> Vec<&TreeEntry> {
+        let recency = |key: &str| {
+            recent
+                .iter()
+                .position(|recent| recent == key)
+                .map_or(0, |index| (RECENT_FILES_LIMIT - index) as i64)
+        };
+        let mut matches: Vec<_> = self
+            .files
+            .iter()
+            .filter_map(|entry| {
+                let score = fuzzy::score(&self.query, &entry.key)?;
+                Some((score + recency(&entry.key), entry))
+            })
+            .collect();
+        matches.sort_by(|(a_score, a), (b_score, b)| b_score.cmp(a_score).then(a.key.cmp(&b.key)));
+        matches
+            .into_iter()
+            .take(QUICK_OPEN_RESULTS)
+            .map(|(_, entry)| entry)
+            .collect()
+    }
+
+    pub fn selected_entry(&self, recent: &[String]) -> Option<&TreeEntry> {
+        self.matches(recent).get(self.selected).copied()
+    }
+
+    /// Moves the selection `offset` rows, stopping at the ends.
+    pub fn move_selection(&mut self, recent: &[String], offset: isize) {
+        let count = self.matches(recent).len();
+        self.selected = self
+            .selected
+            .saturating_add_signed(offset)
+            .min(count.saturating_sub(1));
+        self.refresh_preview(recent);
+    }
+
+    pub fn set_query(&mut self, query: String, recent: &[String]) {
+        self.query = query;
+        self.selected = 0;
+        self.refresh_preview(recent);
+    }
+
+    /// Reads the start of the selected file; unreadable files just have no preview.
+    fn refresh_preview(&mut self, recent: &[String]) {
+        self.preview = self.selected_entry(recent).and_then(|entry| {
+            let contents = std::fs::read_to_string(&entry.path).ok()?;
+            Some(
+                contents
+                    .lines()
+                    .take(QUICK_OPEN_PREVIEW_LINES)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+        });
+    }
+}
+
+/// Operations offered by the file tree's toolbar and context menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TreeAction {
+    NewPacket,
+    NewProcedure,
+    NewFolder,
+    Rename,
+    Duplicate,
+    /// Moves the entry into the project's trash folder.
+    Delete,
+}
+
+impl TreeAction {
+    pub fn creates_entry(self) -> bool {
+        matches!(self, Self::NewPacket | Self::NewProcedure | Self::NewFolder)
+    }
+
+    /// The entry name for `input`, adding the extension new files need.
+    pub fn file_name(self, input: &str) -> String {
+        match self {
+            Self::NewPacket => file_ops::with_extension(input, "json"),
+            Self::NewProcedure => file_ops::with_extension(input, "proc"),
+            _ => input.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for TreeAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NewPacket => write!(f, "New packet"),
+            Self::NewProcedure => write!(f, "New procedure"),
+            Self::NewFolder => write!(f, "New folder"),
+            Self::Rename => write!(f, "Rename"),
+            Self::Duplicate => write!(f, "Duplicate"),
+            Self::Delete => write!(f, "Delete"),
+        }
+    }
+}
+
+/// A file tree operation waiting for a name or confirmation.
+#[derive(Clone, Debug)]
+pub struct PendingTreeAction {
+    pub action: TreeAction,
+    /// Key of the entry acted on; for new entries, the directory they go in (`""` is the root).
+    pub target: String,
+    pub name_input: String,
+    pub validation_error: Option<String>,
+}
+
+/// Which name-taking operation the project picker is asking a name for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProjectAction {
+    Rename,
+    Duplicate,
+}
+
+/// Rename/duplicate/delete/archive state of the project picker.
+#[derive(Default)]
+pub struct ProjectActionsState {
+    /// Operation waiting for a name, if any.
+    pub pending: Option<ProjectAction>,
+    pub name_input: String,
+    pub validation_error: Option<String>,
+    /// Set while the delete confirmation is showing.
+    pub confirming_delete: bool,
+    /// Most recent deletion, kept so it can be undone.
+    pub last_trashed: Option<TrashedProject>,
+    pub show_archived: bool,
+    pub archived_projects: Vec<String>,
+}
+
+impl ProjectActionsState {
+    /// Validates the name input against all projects except `current`, which a rename replaces.
+    pub fn validate_name(&mut self, taken: &[String], current: Option<&str>) {
+        let others: Vec<String> = taken
+            .iter()
+            .filter(|name| Some(name.as_str()) != current)
+            .cloned()
+            .collect();
+        self.validation_error = project::validate_project_name(&self.name_input, &others).err();
+    }
+
+    /// Closes any open prompt without touching the undo or archive state.
+    pub fn reset_prompt(&mut self) {
+        self.pending = None;
+        self.name_input.clear();
+        self.validation_error = None;
+        self.confirming_delete = false;
+    }
+}
+
+#[derive(Default)]
+pub struct ExportProjectState {
+    /// Bundle path as typed; relative paths are resolved against the working directory.
+    pub destination: String,
+    pub include_history: bool,
+    /// Where the last successful export was written.
+    pub exported_to: Option<PathBuf>,
+}
+
+#[derive(Default)]
+pub struct ImportProjectState {
+    /// Bundle path as typed; relative paths are resolved against the working directory.
+    pub source: String,
+}
+
+/// Draft values of the project settings form, applied to the manifest on save.
+#[derive(Default)]
+pub struct ProjectSettingsState {
+    pub name: String,
+    pub description: String,
+    /// Comma-separated tags.
+    pub tags: String,
+    pub default_target: String,
+    /// Comma-separated file extensions.
+    pub file_types: String,
+    /// Comma-separated `.gitignore`-style patterns.
+    pub ignore_patterns: String,
+    pub validation_error: Option<String>,
+    /// Set once the project has been saved as a template while the dialog is open.
+    pub saved_as_template: bool,
+    /// Set while asking whether to replace the template saved earlier under the same name.
+    pub confirm_template_replace: bool,
+}
+
+impl ProjectSettingsState {
+    pub fn from_manifest(manifest: &ProjectManifest) -> Self {
+        Self {
+            name: manifest.name.clone(),
+            description: manifest.description.clone(),
+            tags: manifest.tags.join(", "),
+            default_target: manifest.default_target.clone().unwrap_or_default(),
+            file_types: manifest.file_types.join(", "),
+            ignore_patterns: manifest.ignore_patterns.join(", "),
+            validation_error: None,
+            saved_as_template: false,
+            confirm_template_replace: false,
+        }
+    }
+
+    /// Validates the draft and writes it into `manifest`, leaving it untouched on error.
+    pub fn apply_to(&self, manifest: &mut ProjectManifest) -> Result<(), String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("Display name cannot be empty".to_string());
+        }
+
+        let default_target = match self.default_target.trim() {
+            "" => None,
+            target => {
+                let port = target
+                    .rsplit_once(':')
+                    .and_then(|(host, port)| (!host.is_empty()).then_some(port))
+                    .and_then(|port| port.parse::<u16>().ok());
+                if port.is_none() {
+                    return Err("Default target must look like host:port".to_string());
+                }
+                Some(target.to_string())
+            }
+        };
+
+        let file_types = split_list(&self.file_types)
+            .into_iter()
+            .map(|ext| ext.trim_start_matches('.').to_string())
+            .collect::<Vec<_>>();
+        if file_types.is_empty() {
+            return Err("At least one file type is required".to_string());
+        }
+        let ignore_patterns = split_list(&self.ignore_patterns);
+        file_tree::validate_ignore_patterns(&ignore_patterns)?;
+
+        manifest.name = name.to_string();
+        manifest.description = self.description.trim().to_string();
+        manifest.tags = split_list(&self.tags);
+        manifest.default_target = default_target;
+        manifest.file_types = file_types;
+        manifest.ignore_patterns = ignore_patterns;
+        manifest.touch();
+        Ok(())
+    }
+}
+
+/// Splits a comma-separated list, dropping empty items.
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+pub struct ThemeState {
+    /// Built-in themes followed by custom ones from the config directory.
+    pub available: Vec<Theme>,
+    pub active: Theme,
+}
+
+impl Default for ThemeState {
+    fn default() -> Self {
+        Self {
+            available: themes::available_themes(Vec::new()),
+            active: themes::DEFAULT_THEME,
+        }
+    }
+}
+
+/// Errors waiting to be shown in the banner, most recent last.
+#[derive(Default)]
+pub struct ErrorState {
+    pub notices: Vec<ErrorNotice>,
+}
+
+pub struct ErrorNotice {
+    pub error: DispatchError,
+    pub retry: Option<Message>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tab per name, with the last one in front.
+    fn tabs(names: &[&str]) -> WorkspaceTabs {
+        let mut tabs = WorkspaceTabs::default();
+        for name in names {
+            tabs.open(ProjectState {
+                current_project: name.to_string(),
+                ..ProjectState::default()
+            });
+        }
+        tabs
+    }
+
+    fn active_name(tabs: &WorkspaceTabs) -> Option<&str> {
+        tabs.active()
+            .map(|workspace| workspace.project.current_project.as_str())
+    }
+
+    #[test]
+    fn closing_the_active_tab_brings_the_next_one_forward() {
+        let mut tabs = tabs(&["a", "b", "c"]);
+        tabs.active = 1;
+        let id = tabs.open[1].id;
+        assert!(tabs.close(id).is_some());
+        assert_eq!(active_name(&tabs), Some("c"));
+
+        let id = tabs.open[1].id;
+        tabs.close(id);
+        assert_eq!(active_name(&tabs), Some("a"));
+    }
+
+    #[test]
+    fn closing_an_earlier_tab_keeps_the_active_one() {
+        let mut tabs = tabs(&["a", "b", "c"]);
+        let id = tabs.open[0].id;
+        tabs.close(id);
+        assert_eq!(active_name(&tabs), Some("c"));
+        assert_eq!(tabs.active, 1);
+    }
+
+    #[test]
+    fn closing_the_last_tab_leaves_none() {
+        let mut tabs = tabs(&["a"]);
+        let id = tabs.open[0].id;
+        assert!(tabs.close(id).is_some());
+        assert!(tabs.active().is_none());
+        assert!(tabs.close(id).is_none());
+        tabs.cycle(1);
+        assert!(tabs.active().is_none());
+    }
+
+    #[test]
+    fn cycling_wraps_in_both_directions() {
+        let mut tabs = tabs(&["a", "b", "c"]);
+        tabs.cycle(1);
+        assert_eq!(active_name(&tabs), Some("a"));
+        tabs.cycle(-1);
+        assert_eq!(active_name(&tabs), Some("c"));
+        tabs.active = 0;
+        tabs.cycle(-1);
+        assert_eq!(active_name(&tabs), Some("c"));
+        tabs.cycle(-4);
+        assert_eq!(active_name(&tabs), Some("b"));
+    }
+
+    #[test]
+    fn only_the_latest_layout_save_is_due() {
+        let mut tabs = tabs(&["a", "b"]);
+        let id = tabs.open[0].id;
+        tabs.open[0].layout.revision = 3;
+
+        assert!(tabs.due_layout_save(id, 2).is_none());
+        assert!(tabs.due_layout_save(id, 3).is_some());
+
+        tabs.close(id);
+        assert!(tabs.due_layout_save(id, 3).is_none());
+    }
+
+    fn quick_open(keys: &[&str]) -> QuickOpenState {
+        let files = keys
+            .iter()
+            .map(|key| TreeEntry {
+                path: PathBuf::from(key),
+                key: key.to_string(),
+                depth: key.matches('/').count(),
+                is_directory: false,
+            })
+            .collect();
+        QuickOpenState {
+            files,
+            ..QuickOpenState::default()
+        }
+    }
+
+    fn match_keys<'a>(state: &'a QuickOpenState, recent: &[String]) -> Vec<&'a str> {
+        state
+            .matches(recent)
+            .into_iter()
+            .map(|entry| entry.key.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn recent_files_win_equal_matches() {
+        let mut state = quick_open(&["admin/login.json", "users/login.json"]);
+        state.query = "login".to_string();
+        assert_eq!(
+            match_keys(&state, &[]),
+            ["admin/login.json", "users/login.json"]
+        );
+
+        let recent = vec!["users/login.json".to_string()];
+        assert_eq!(
+            match_keys(&state, &recent),
+            ["users/login.json", "admin/login.json"]
+        );
+    }
+
+    #[test]
+    fn empty_query_lists_recent_files_first() {
+        let state = quick_open(&["a.json", "b.json", "c.json", "d.json"]);
+        let recent = vec!["c.json".to_string(), "b.json".to_string()];
+        assert_eq!(
+            match_keys(&state, &recent),
+            ["c.json", "b.json", "a.json", "d.json"]
+        );
+    }
 }
@@ -0,0 +1,317 @@
+use std::{fmt, path::Path};
+
+use crate::{
+    DispatchError,
+    config::list_directories,
+    project::{self, ProjectManifest},
+};
+
+/// Directory (inside the config directory) holding templates saved from existing projects.
+pub const TEMPLATES_DIR: &str = "templates";
+
+/// Server address the built-in templates point their procedures at.
+const STARTER_TARGET: &str = "127.0.0.1:7000";
+
+/// Starting point for a new project, picked in the create dialog.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub enum ProjectTemplate {
+    #[default]
+    Empty,
+    EchoSmokeTest,
+    AuthSessionFlow,
+    /// A project saved under `<config_dir>/templates/<name>`.
+    Saved(String),
+}
+
+impl ProjectTemplate {
+    pub const BUILT_IN: [Self; 3] = [Self::Empty, Self::EchoSmokeTest, Self::AuthSessionFlow];
+
+    /// One-line summary shown under the template picker.
+    pub fn summary(&self) -> &str {
+        match self {
+            Self::Empty => "An empty project with only a manifest",
+            Self::EchoSmokeTest => "A ping packet and a procedure expecting the server to echo it",
+            Self::AuthSessionFlow => "Login, session check and logout packets with a procedure",
+            Self::Saved(_) => "Packets, procedures and settings saved from another project",
+        }
+    }
+
+    fn starter_files(&self) -> &'static [StarterFile] {
+        match self {
+            Self::EchoSmokeTest => ECHO_SMOKE_TEST,
+            Self::AuthSessionFlow => AUTH_SESSION_FLOW,
+            Self::Empty | Self::Saved(_) => &[],
+        }
+    }
+}
+
+impl fmt::Display for ProjectTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Empty"),
+            Self::EchoSmokeTest => write!(f, "Echo server smoke test"),
+            Self::AuthSessionFlow => write!(f, "Auth + session flow"),
+            Self::Saved(name) => write!(f, "Saved: {name}"),
+        }
+    }
+}
+
+/// A file written into the project by a built-in template.
+struct StarterFile {
+    path: &'static str,
+    contents: &'static str,
+}
+
+const ECHO_SMOKE_TEST: &[StarterFile] = &[
+    StarterFile {
+        path: "packets/ping.json",
+        contents: r#"{
+  "type": "ping",
+  "payload": "hello"
+}
+"#,
+    },
+    StarterFile {
+        path: "procedures/echo_smoke.proc",
+        contents: r#"# Sends a ping and expects the server to echo the payload back.
+version 1
+connect
+send packets/ping.json
+expect payload == "hello" within 2s
+disconnect
+"#,
+    },
+];
+
+const AUTH_SESSION_FLOW: &[StarterFile] = &[
+    StarterFile {
+        path: "packets/login.json",
+        contents: r#"{
+  "type": "login",
+  "username": "test-user",
+  "password": "change-me"
+}
+"#,
+    },
+    StarterFile {
+        path: "packets/whoami.json",
+        contents: r#"{
+  "type": "whoami",
+  "token": "${token}"
+}
+"#,
+    },
+    StarterFile {
+        path: "packets/logout.json",
+        contents: r#"{
+  "type": "logout",
+  "token": "${token}"
+}
+"#,
+    },
+    StarterFile {
+        path: "procedures/login_session.proc",
+        contents: r#"# Logs in, checks the session token is accepted, then logs out.
+version 1
+connect
+send packets/login.json
+expect status == "ok" within 5s
+capture token = session.token
+send packets/whoami.json
+expect user == "test-user" within 2s
+send packets/logout.json
+expect status == "ok" within 2s
+disconnect
+"#,
+    },
+];
+
+/// Built-in templates followed by the ones saved in `<config_dir>/templates`.
+pub fn available_templates(config_dir: &Path) -> Result<Vec<ProjectTemplate>, DispatchError> {
+    let mut templates = ProjectTemplate::BUILT_IN.to_vec();
+    let dir = config_dir.join(TEMPLATES_DIR);
+    if dir.is_dir() {
+        let mut saved = list_directories(&dir)?;
+        saved.sort();
+        templates.extend(saved.into_iter().map(ProjectTemplate::Saved));
+    }
+    Ok(templates)
+}
+
+/// Fills the freshly created, empty `project_dir` from `template` and writes its manifest.
+pub fn scaffold(
+    template: &ProjectTemplate,
+    project_dir: &Path,
+    name: &str,
+    config_dir: &Path,
+) -> Result<(), DispatchError> {
+    let mut manifest = ProjectManifest::new(name);
+    match template {
+        ProjectTemplate::Empty => {}
+        ProjectTemplate::EchoSmokeTest | ProjectTemplate::AuthSessionFlow => {
+            for file in template.starter_files() {
+                let path = project_dir.join(file.path);
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).map_err(|e| {
+                        DispatchError::io(format!("creating {}", parent.display()), e)
+                    })?;
+                }
+                std::fs::write(&path, file.contents)
+                    .map_err(|e| DispatchError::io(format!("writing {}", path.display()), e))?;
+            }
+            manifest.description = template.summary().to_string();
+            manifest.tags = vec!["starter".to_string()];
+            manifest.default_target = Some(STARTER_TARGET.to_string());
+        }
+        ProjectTemplate::Saved(template_name) => {
+            let template_dir = config_dir.join(TEMPLATES_DIR).join(template_name);
+            let entries = std::fs::read_dir(&template_dir)
+                .map_err(|e| DispatchError::io(format!("reading template '{template_name}'"), e))?;
+            for entry in entries.flatten() {
+                let target = project_dir.join(entry.file_name());
+                if entry.path().is_dir() {
+                    project::copy_dir_all(&entry.path(), &target)?;
+                } else {
+                    std::fs::copy(entry.path(), &target).map_err(|e| {
+                        DispatchError::io(format!("copying {}", entry.path().display()), e)
+                    })?;
+                }
+            }
+            // Keep a saved manifest's settings but give the project its own identity
+            if template_dir.join(project::MANIFEST_FILE).exists() {
+                let saved = ProjectManifest::load(&template_dir)?;
+                manifest = ProjectManifest {
+                    name: manifest.name,
+                    created: manifest.created,
+                    modified: manifest.modified,
+                    ..saved
+                };
+            }
+        }
+    }
+    manifest.save(project_dir)
+}
+
+/// Whether a template called `name` has been saved already.
+pub fn template_exists(config_dir: &Path, name: &str) -> bool {
+    config_dir.join(TEMPLATES_DIR).join(name).exists()
+}
+
+/// Saves the project's manifest and tracked files as a template named after the project.
+///
+/// An earlier template of the same name is an error unless `replace` is set.
+pub fn save_as_template(
+    project_dir: &Path,
+    project_name: &str,
+    manifest: &ProjectManifest,
+    config_dir: &Path,
+    replace: bool,
+) -> Result<(), DispatchError> {
+    let io_error = |e| DispatchError::io("saving the project as a template", e);
+
+    let template_dir = config_dir.join(TEMPLATES_DIR).join(project_name);
+    if template_dir.exists() {
+        if !replace {
+            return Err(DispatchError::Project(format!(
+                "a template named '{project_name}' already exists"
+            )));
+        }
+        std::fs::remove_dir_all(&template_dir).map_err(io_error)?;
+    }
+    std::fs::create_dir_all(&template_dir).map_err(io_error)?;
+
+    for file in project::project_files(project_dir, &manifest.file_types)? {
+        let relative = file.strip_prefix(project_dir).unwrap_or(&file);
+        let target = template_dir.join(relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        std::fs::copy(&file, &target).map_err(io_error)?;
+    }
+    manifest.save(&template_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starter_procedures_parse() {
+        let procedures: Vec<_> = ProjectTemplate::BUILT_IN
+            .iter()
+            .flat_map(ProjectTemplate::starter_files)
+            .filter(|file| file.path.ends_with(".proc"))
+            .collect();
+        assert_eq!(procedures.len(), 2);
+        for file in procedures {
+            let procedure = crate::procedure::parse(file.contents)
+                .unwrap_or_else(|error| panic!("{}", error.in_file(file.path)));
+            assert_eq!(procedure.version, crate::procedure::FORMAT_VERSION);
+        }
+    }
+
+    #[test]
+    fn saved_template_scaffolds_a_copy() {
+        let root =
+            std::env::temp_dir().join(format!("tnet-dispatch-templates-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let config_dir = root.join("config");
+        let original = root.join("DATA/original");
+        let copy = root.join("DATA/copy");
+        std::fs::create_dir_all(&original).unwrap();
+        std::fs::create_dir_all(&copy).unwrap();
+
+        scaffold(
+            &ProjectTemplate::AuthSessionFlow,
+            &original,
+            "original",
+            &config_dir,
+        )
+        .unwrap();
+        let manifest = ProjectManifest::load(&original).unwrap();
+        assert_eq!(manifest.default_target.as_deref(), Some(STARTER_TARGET));
+
+        save_as_template(&original, "original", &manifest, &config_dir, false).unwrap();
+        assert!(save_as_template(&original, "original", &manifest, &config_dir, false).is_err());
+        save_as_template(&original, "original", &manifest, &config_dir, true).unwrap();
+        let templates = available_templates(&config_dir).unwrap();
+        let saved = ProjectTemplate::Saved("original".to_string());
+        assert!(templates.contains(&saved));
+
+        scaffold(&saved, &copy, "copy", &config_dir).unwrap();
+        assert!(copy.join("packets/whoami.json").is_file());
+        let copied = ProjectManifest::load(&copy).unwrap();
+        assert_eq!(copied.name, "copy");
+        assert_eq!(copied.default_target, manifest.default_target);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn bare_template_is_used_as_is() {
+        let root = std::env::temp_dir().join(format!(
+            "tnet-dispatch-templates-bare-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+        let config_dir = root.join("config");
+        let bare = config_dir.join(TEMPLATES_DIR).join("bare");
+        std::fs::create_dir_all(&bare).unwrap();
+        std::fs::write(bare.join("notes.txt"), "").unwrap();
+        let from_bare = root.join("DATA/from-bare");
+        std::fs::create_dir_all(&from_bare).unwrap();
+
+        scaffold(
+            &ProjectTemplate::Saved("bare".to_string()),
+            &from_bare,
+            "from-bare",
+            &config_dir,
+        )
+        .unwrap();
+        assert!(!bare.join(project::MANIFEST_FILE).exists());
+        assert!(from_bare.join("notes.txt").is_file());
+        assert_eq!(ProjectManifest::load(&from_bare).unwrap().name, "from-bare");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
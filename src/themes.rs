@@ -0,0 +1,157 @@
+use std::path::Path;
+
+use iced::{Color, Theme, theme::Palette};
+use serde::Deserialize;
+
+use crate::DispatchError;
+
+/// Theme used when the config names none or names one that no longer exists.
+pub const DEFAULT_THEME: Theme = Theme::CatppuccinMocha;
+
+/// Directory (inside the config directory) scanned for custom theme files.
+pub const THEMES_DIR: &str = "themes";
+
+/// A custom palette as written in `themes/<anything>.toml`, colors as `#rrggbb` hex strings.
+///
+/// ```toml
+/// name = "Solarized Light"
+/// background = "#fdf6e3"
+/// text = "#657b83"
+/// primary = "#268bd2"
+/// success = "#859900"
+/// danger = "#dc322f"
+/// ```
+#[derive(Deserialize)]
+struct ThemeFile {
+    name: String,
+    background: String,
+    text: String,
+    primary: String,
+    success: String,
+    danger: String,
+}
+
+impl ThemeFile {
+    fn into_theme(self) -> Result<Theme, String> {
+        let color = |field: &str, value: &str| {
+            Color::parse(value).ok_or_else(|| format!("invalid color '{value}' for {field}"))
+        };
+        let palette = Palette {
+            background: color("background", &self.background)?,
+            text: color("text", &self.text)?,
+            primary: color("primary", &self.primary)?,
+            success: color("success", &self.success)?,
+            danger: color("danger", &self.danger)?,
+        };
+        Ok(Theme::custom(self.name, palette))
+    }
+}
+
+/// Loads every custom theme in `<config_dir>/themes`.
+///
+/// Files that fail to parse are skipped and returned as errors so the rest still load.
+pub fn load_custom_themes(config_dir: &Path) -> (Vec<Theme>, Vec<DispatchError>) {
+    let mut themes = Vec::new();
+    let mut errors = Vec::new();
+
+    let entries = match std::fs::read_dir(config_dir.join(THEMES_DIR)) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return (themes, errors),
+        Err(error) => {
+            errors.push(DispatchError::io("reading the themes directory", error));
+            return (themes, errors);
+        }
+    };
+
+    let mut paths: Vec<_> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let theme = std::fs::read_to_string(&path)
+            .map_err(|e| DispatchError::io(format!("reading {}", path.display()), e))
+            .and_then(|contents| {
+                toml::from_str::<ThemeFile>(&contents)
+                    .map_err(|e| e.to_string())
+                    .and_then(ThemeFile::into_theme)
+                    .map_err(|e| DispatchError::Config(format!("theme {}: {e}", path.display())))
+            });
+        match theme {
+            Ok(theme) => themes.push(theme),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    (themes, errors)
+}
+
+/// All built-in themes followed by `custom` ones.
+pub fn available_themes(custom: Vec<Theme>) -> Vec<Theme> {
+    Theme::ALL.iter().cloned().chain(custom).collect()
+}
+
+/// Finds a theme by its display name.
+pub fn find_theme(available: &[Theme], name: &str) -> Option<Theme> {
+    available
+        .iter()
+        .find(|theme| theme.to_string() == name)
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_custom_themes_and_reports_bad_ones() {
+        let config_dir =
+            std::env::temp_dir().join(format!("tnet-dispatch-themes-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&config_dir);
+
+        // No themes directory is not an error
+        let (themes, errors) = load_custom_themes(&config_dir);
+        assert!(themes.is_empty() && errors.is_empty());
+
+        let dir = config_dir.join(THEMES_DIR);
+        std::fs::create_dir_all(&dir).unwrap();
+        let theme = |name: &str, primary: &str| {
+            format!(
+                "name = \"{name}\"\nbackground = \"#fdf6e3\"\ntext = \"#657b83\"\n\
+                 primary = \"{primary}\"\nsuccess = \"#859900\"\ndanger = \"#dc322f\"\n"
+            )
+        };
+        std::fs::write(dir.join("light.toml"), theme("Solarized Light", "#268bd2")).unwrap();
+        std::fs::write(dir.join("broken.toml"), theme("Broken", "not-a-color")).unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let (themes, errors) = load_custom_themes(&config_dir);
+        assert_eq!(themes.len(), 1);
+        assert_eq!(themes[0].to_string(), "Solarized Light");
+        assert_eq!(
+            themes[0].palette().primary,
+            Color::parse("#268bd2").unwrap()
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0]
+                .to_string()
+                .contains("invalid color 'not-a-color' for primary")
+        );
+
+        let available = available_themes(themes);
+        assert_eq!(
+            find_theme(&available, "Solarized Light").map(|theme| theme.to_string()),
+            Some("Solarized Light".to_string())
+        );
+        assert_eq!(
+            find_theme(&available, &DEFAULT_THEME.to_string()),
+            Some(DEFAULT_THEME)
+        );
+        assert_eq!(find_theme(&available, "Missing"), None);
+
+        std::fs::remove_dir_all(&config_dir).unwrap();
+    }
+}
@@ -1,185 +1,138 @@
 use iced::{
-    Alignment, Element, Length,
-    widget::{button, column, container, scrollable, text},
+    Alignment, Element, Font, Length, keyboard,
+    widget::{button, column, container, row, scrollable, text, text_editor},
 };
-use std::path::PathBuf;
 
-use crate::app::{Dispatcher, Message};
+use crate::Message;
+use crate::editor::Language;
+use crate::file_info::FileSummary;
+use crate::highlight::{self, SourceHighlighter};
+use crate::states::{ProcedureRun, RunState, StepStatus, Workspace};
 
-pub fn main_view_panel(_state: &Dispatcher) -> Element<Message> {
-    container(scrollable(
-        column![
-            text("Main View Area").size(20),
-            text("This is the main content area where the primary workspace is displayed"),
-            text("Content line 1"),
-            text("Content line 2"),
-            text("Content line 3"),
-            text("Content line 4"),
-            text("Content line 5")
-        ]
-        .spacing(10)
-        .width(Length::Fill)
-        .align_x(Alignment::Start)
-        .padding([15, 15]),
-    ))
-    .width(Length::Fill)
-    .height(Length::Fill)
-    .padding(10)
-    .into()
-}
+use super::format;
 
-pub fn inspector_panel(_state: &Dispatcher) -> Element<Message> {
-    container(scrollable(
-        column![
-            text("Inspector Panel").size(20),
-            text("Properties and details for selected items appear here"),
-            text("Property 1: Value"),
-            text("Property 2: Value"),
-            text("Property 3: Value")
-        ]
-        .spacing(10)
-        .width(Length::Fill)
-        .align_x(Alignment::Start)
-        .padding([15, 15]),
-    ))
-    .width(Length::Fill)
-    .height(Length::Fill)
-    .padding(10)
-    .into()
-}
+pub fn main_view_panel(workspace: &Workspace) -> Element<'_, Message> {
+    let (Some(opened), Some(editor)) = (&workspace.opened_file, &workspace.editor) else {
+        return placeholder("Select a packet or procedure in the file tree to open it");
+    };
 
-pub fn file_tree_panel(state: &Dispatcher) -> Element<Message> {
-    let proj_dir = state.states.project.current_project_path.clone();
-    
-    // Structure to hold file tree entry data
-    #[derive(Debug)]
-    struct TreeEntry {
-        path: PathBuf,
-        is_directory: bool,
-        indent_level: usize,
-        is_last_in_level: bool,
+    let title = if editor.dirty {
+        format!("● {}", opened.key)
+    } else {
+        opened.key.clone()
+    };
+    let action = |label, message: Option<Message>| {
+        button(text(label).size(12))
+            .padding([3, 8])
+            .style(button::secondary)
+            .on_press_maybe(message)
+    };
+    let is_json = editor.language == Language::Json;
+    let valid_json = is_json && editor.error.is_none();
+    let mut toolbar = row![text(title).size(16).width(Length::Fill)]
+        .spacing(8)
+        .align_y(Alignment::Center);
+    if is_json {
+        toolbar = toolbar
+            .push(action(
+                "Pretty-print",
+                valid_json.then_some(Message::ReformatJson(true)),
+            ))
+            .push(action(
+                "Minify",
+                valid_json.then_some(Message::ReformatJson(false)),
+            ));
     }
-    
-    // Collect file entries for display
-    let mut tree_entries: Vec<TreeEntry> = Vec::new();
-
-    // Helper function to build tree entry data
-    fn build_tree_entries(
-        dir_path: &std::path::Path,
-        base_dir: &std::path::Path,
-        indent_level: usize,
-        entries: &mut Vec<TreeEntry>,
-    ) {
-        if let Ok(dir_entries) = std::fs::read_dir(dir_path) {
-            // Convert to Vec for easier processing and sort
-            let mut dir_entries: Vec<_> = dir_entries.flatten().collect();
-            // Sort directories first, then files
-            dir_entries.sort_by(|a, b| {
-                let a_is_dir = a.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
-                let b_is_dir = b.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
-                if a_is_dir && !b_is_dir {
-                    std::cmp::Ordering::Less
-                } else if !a_is_dir && b_is_dir {
-                    std::cmp::Ordering::Greater
-                } else {
-                    a.file_name().cmp(&b.file_name())
-                }
-            });
-            
-            let entry_count = dir_entries.len();
-            
-            for (idx, entry) in dir_entries.iter().enumerate() {
-                let is_last = idx == entry_count - 1;
-                let path = entry.path();
-                
-                if let Ok(file_type) = entry.file_type() {
-                    let file_name = path.file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("Unknown");
-                    
-                    if file_type.is_dir() {
-                        // Add directory entry
-                        entries.push(TreeEntry {
-                            path: path.clone(),
-                            is_directory: true,
-                            indent_level,
-                            is_last_in_level: is_last,
-                        });
-                        
-                        // Process subdirectory
-                        build_tree_entries(
-                            &path,
-                            base_dir,
-                            indent_level + 1,
-                            entries,
-                        );
-                    } else if file_type.is_file() {
-                        // Only add files with .json or .proc extensions
-                        if file_name.ends_with(".json") || file_name.ends_with(".proc") {
-                            entries.push(TreeEntry {
-                                path: path.clone(),
-                                is_directory: false,
-                                indent_level,
-                                is_last_in_level: is_last,
-                            });
-                        }
-                    }
-                }
+    toolbar = toolbar
+        .push(action(
+            "Revert",
+            editor.dirty.then_some(Message::RevertOpenedFile),
+        ))
+        .push(
+            button(text("Save").size(12))
+                .padding([3, 8])
+                .on_press_maybe(editor.dirty.then_some(Message::SaveOpenedFile)),
+        );
+
+    let input = text_editor(&editor.content)
+        .font(Font::MONOSPACE)
+        .size(14)
+        .height(Length::Fill)
+        .on_action(Message::EditorAction)
+        .key_binding(|press| {
+            // Ctrl+S saves instead of reaching the default bindings
+            if press.modifiers.command()
+                && matches!(press.key.as_ref(), keyboard::Key::Character("s"))
+            {
+                return Some(text_editor::Binding::Custom(Message::SaveOpenedFile));
             }
-        }
+            text_editor::Binding::from_key_press(press)
+        })
+        .highlight_with::<SourceHighlighter>(editor.highlight_settings(), highlight::to_format);
+
+    let mut panel = column![toolbar, input].spacing(8);
+    if let Some(error) = &editor.error {
+        panel = panel.push(
+            text(format!("{}: {}", opened.key, error.message))
+                .size(13)
+                .style(text::danger),
+        );
     }
 
-    // Build the tree entries starting from the project directory
-    build_tree_entries(&proj_dir, &proj_dir, 0, &mut tree_entries);
-    
-    // Create elements for the file tree
-    let mut tree_elements = Vec::new();
-    tree_elements.push(text("File Tree").size(20).into());
-    tree_elements.push(text("Project structure and files").into());
-    
-    // Add all tree entries as clickable elements
-    for entry in &tree_entries {
-        let file_name = entry.path.file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("Unknown");
-            
-        // Create indentation prefix
-        let mut prefix = String::new();
-        for i in 0..entry.indent_level {
-            // Find if any parent at this level is a last entry
-            let is_last_parent = tree_entries.iter()
-                .filter(|e| e.indent_level == i && e.is_directory)
-                .any(|e| e.is_last_in_level);
-                
-            if is_last_parent {
-                prefix.push_str("    "); // Space for last item branch
-            } else {
-                prefix.push_str("│   "); // Vertical line for continuing branch
-            }
-        }
-        
-        // Add the connector for this entry
-        let connector = if entry.is_last_in_level { "└── " } else { "├── " };
-        
-        // Create the full display text
-        let display_text = if entry.is_directory {
-            format!("{}{}{}/", prefix, connector, file_name)
-        } else {
-            format!("{}{}{}", prefix, connector, file_name)
-        };
-        
-        // Create a clickable button for the entry
-        let btn = button(text(display_text))
-            .padding([2, 5])
-            .width(Length::Fill)
-            .on_press(Message::SelectedProject);
-            
-        tree_elements.push(btn.into());
+    container(panel)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(10)
+        .into()
+}
+
+pub fn inspector_panel(workspace: &Workspace) -> Element<'_, Message> {
+    let Some(opened) = &workspace.opened_file else {
+        return placeholder("Details of the opened file appear here");
+    };
+    let details = &opened.details;
+
+    let property = |label: &str, value: String| text(format!("{label}: {value}")).size(14);
+
+    let mut properties = column![
+        text(&opened.key).size(16),
+        property("Size", format::file_size(details.size)),
+    ]
+    .spacing(6);
+    if let Some(modified) = details.modified {
+        properties = properties.push(property(
+            "Modified",
+            format!(
+                "{} ({})",
+                format::date_time(modified),
+                format::relative_time(modified)
+            ),
+        ));
     }
-    
+
+    properties = match &details.summary {
+        FileSummary::Packet { kind, fields } => properties
+            .push(property(
+                "Packet type",
+                kind.clone().unwrap_or_else(|| "none".to_string()),
+            ))
+            .push(property("Fields", fields.join(", "))),
+        FileSummary::JsonValue { description } => {
+            properties.push(property("JSON", description.clone()))
+        }
+        FileSummary::InvalidJson { error } => {
+            properties.push(property("Invalid JSON", error.clone()).style(text::danger))
+        }
+        FileSummary::Procedure { steps } => properties.push(property("Steps", steps.to_string())),
+        FileSummary::InvalidProcedure { error } => {
+            properties.push(property("Invalid procedure", error.clone()).style(text::danger))
+        }
+        FileSummary::Other => properties,
+    };
+    properties = properties.push_maybe(procedure_run(workspace));
+
     container(scrollable(
-        column(tree_elements)
-            .spacing(5)
+        properties
             .width(Length::Fill)
             .align_x(Alignment::Start)
             .padding([15, 15]),
@@ -189,3 +142,85 @@ pub fn file_tree_panel(state: &Dispatcher) -> Element<Message> {
     .padding(10)
     .into()
 }
+
+/// Run controls for the opened procedure and the steps of the tab's last run.
+fn procedure_run(workspace: &Workspace) -> Option<Element<'_, Message>> {
+    let opened = workspace.opened_file.as_ref()?;
+    let editor = workspace.editor.as_ref()?;
+    let run = workspace.run.as_ref();
+    let is_procedure = matches!(opened.details.summary, FileSummary::Procedure { .. })
+        && editor.language == Language::Procedure;
+    if !is_procedure && run.is_none() {
+        return None;
+    }
+
+    let active = run.is_some_and(ProcedureRun::is_active);
+    let can_run = is_procedure && !active && !editor.dirty && editor.error.is_none();
+    let action = |label, message: Option<Message>| {
+        button(text(label).size(12))
+            .padding([3, 8])
+            .style(button::secondary)
+            .on_press_maybe(message)
+    };
+    let mut controls = row![
+        button(text("Run").size(12))
+            .padding([3, 8])
+            .on_press_maybe(can_run.then_some(Message::RunProcedure))
+    ]
+    .spacing(8)
+    .align_y(Alignment::Center);
+    if let Some(run) = run.filter(|run| run.is_active()) {
+        let paused = run.state == RunState::Paused;
+        controls = controls
+            .push(action(
+                if paused { "Resume" } else { "Pause" },
+                Some(Message::SetProcedurePaused(!paused)),
+            ))
+            .push(action("Cancel", Some(Message::CancelProcedure)));
+    }
+    if is_procedure && editor.dirty {
+        controls = controls.push(text("Save to run").size(12).style(text::secondary));
+    }
+
+    let mut section = column![text("Procedure run").size(16), controls].spacing(6);
+    if let Some(run) = run {
+        let state = match run.state {
+            RunState::Running => "running",
+            RunState::Paused => "paused before the next step",
+            RunState::Passed => "passed",
+            RunState::Failed => "failed",
+            RunState::Cancelled => "cancelled",
+        };
+        section = section
+            .push(text(format!("{}: {state}", run.key)).size(13))
+            .extend(run.steps.iter().map(|step| {
+                let (status, style): (String, fn(&iced::Theme) -> text::Style) = match &step.status
+                {
+                    StepStatus::Pending => ("○ pending".to_string(), text::secondary),
+                    StepStatus::Running => ("◐ running".to_string(), text::primary),
+                    StepStatus::Passed(elapsed) => {
+                        (format!("✓ {}", format::elapsed(*elapsed)), text::success)
+                    }
+                    StepStatus::Failed(elapsed, error) => (
+                        format!("✗ {}: {error}", format::elapsed(*elapsed)),
+                        text::danger,
+                    ),
+                    StepStatus::Skipped => ("– skipped".to_string(), text::secondary),
+                };
+                column![
+                    text(&step.source).font(Font::MONOSPACE).size(12),
+                    text(status).size(12).style(style)
+                ]
+                .into()
+            }));
+    }
+    Some(section.padding([10, 0]).into())
+}
+
+fn placeholder(message: &str) -> Element<'_, Message> {
+    container(text(message).size(14).style(text::secondary))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(25)
+        .into()
+}
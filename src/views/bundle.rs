@@ -0,0 +1,105 @@
+use iced::widget::{button, checkbox, column, container, row, text, text_input};
+use iced::{Element, Length};
+
+use crate::bundle::BUNDLE_EXTENSION;
+use crate::states::Workspace;
+use crate::{Dispatcher, Message};
+
+pub fn export_project_dialog<'a>(
+    state: &'a Dispatcher,
+    workspace: &'a Workspace,
+) -> Element<'a, Message> {
+    let export = &state.states.export_project;
+
+    let title = text("Export Project").size(24);
+    let subtitle = text(format!(
+        "Bundle '{}' into a single .{BUNDLE_EXTENSION} file",
+        workspace.project.current_project
+    ))
+    .size(14);
+
+    let destination = column![
+        text("Destination").size(14),
+        text_input("project.tnetpkg", &export.destination)
+            .padding(10)
+            .width(Length::Fill)
+            .on_input(Message::ExportDestinationChanged)
+            .on_submit(Message::ConfirmExportProject)
+    ]
+    .spacing(5);
+
+    let include_history = checkbox("Include run history", export.include_history)
+        .on_toggle(Message::ExportIncludeHistoryToggled);
+
+    let status = match &export.exported_to {
+        Some(path) => text(format!("Exported to {}", path.display()))
+            .size(14)
+            .style(text::success),
+        // Use a non-breaking space to maintain consistent height without visible text
+        None => text("\u{00A0}").size(14),
+    };
+
+    let close_button = button(text(if export.exported_to.is_some() {
+        "Done"
+    } else {
+        "Cancel"
+    }))
+    .padding(10)
+    .on_press(Message::CloseExportProject);
+
+    let export_button = button(text("Export")).padding(10).on_press_maybe(
+        (!export.destination.trim().is_empty()).then_some(Message::ConfirmExportProject),
+    );
+
+    dialog(column![
+        title,
+        subtitle,
+        destination,
+        include_history,
+        status,
+        row![close_button, export_button].spacing(10).padding(10)
+    ])
+}
+
+pub fn import_project_dialog(state: &Dispatcher) -> Element<'_, Message> {
+    let import = &state.states.import_project;
+
+    let title = text("Import Project").size(24);
+    let subtitle = text(format!(
+        "Unpack a .{BUNDLE_EXTENSION} bundle into the data folder; \
+         a suffix is added if the name is taken"
+    ))
+    .size(14);
+
+    let source = column![
+        text("Bundle file").size(14),
+        text_input("project.tnetpkg", &import.source)
+            .padding(10)
+            .width(Length::Fill)
+            .on_input(Message::ImportSourceChanged)
+            .on_submit(Message::ConfirmImportProject)
+    ]
+    .spacing(5);
+
+    let cancel_button = button(text("Cancel"))
+        .padding(10)
+        .on_press(Message::CancelImportProject);
+
+    let import_button = button(text("Import")).padding(10).on_press_maybe(
+        (!import.source.trim().is_empty()).then_some(Message::ConfirmImportProject),
+    );
+
+    dialog(column![
+        title,
+        subtitle,
+        source,
+        row![cancel_button, import_button].spacing(10).padding(10)
+    ])
+}
+
+fn dialog(content: iced::widget::Column<'_, Message>) -> Element<'_, Message> {
+    container(content.spacing(15).padding(20).width(Length::Fill))
+        .width(Length::Fixed(500.00))
+        .center_x(Length::Fixed(500.00))
+        .into()
+}
@@ -0,0 +1,58 @@
+use iced::widget::{button, column, container, row, text};
+use iced::{Background, Border, Element, Length};
+
+use crate::app::Message;
+use crate::states::ErrorState;
+
+/// Non-blocking banner showing the most recent error with retry/dismiss actions.
+pub fn error_banner(errors: &ErrorState) -> Option<Element<'_, Message>> {
+    let notice = errors.notices.last()?;
+
+    let mut message = column![text(notice.error.to_string()).size(14)];
+    if errors.notices.len() > 1 {
+        message = message.push(
+            text(format!(
+                "{} more error(s) pending",
+                errors.notices.len() - 1
+            ))
+            .size(12),
+        );
+    }
+
+    let mut actions = row![].spacing(10);
+    if notice.retry.is_some() {
+        actions = actions.push(
+            button(text("Retry"))
+                .padding([4, 10])
+                .on_press(Message::RetryAfterError),
+        );
+    }
+    actions = actions.push(
+        button(text("Dismiss"))
+            .padding([4, 10])
+            .on_press(Message::DismissError),
+    );
+
+    Some(
+        container(
+            row![message.width(Length::Fill), actions]
+                .spacing(20)
+                .align_y(iced::Alignment::Center),
+        )
+        .padding(10)
+        .width(Length::Fill)
+        .style(|theme: &iced::Theme| {
+            let danger = theme.extended_palette().danger.base;
+            container::Style {
+                text_color: Some(danger.text),
+                background: Some(Background::Color(danger.color)),
+                border: Border {
+                    radius: 3.0.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        })
+        .into(),
+    )
+}
@@ -0,0 +1,273 @@
+use std::sync::LazyLock;
+
+use iced::widget::{
+    button, checkbox, column, container, mouse_area, row, scrollable, text, text_input,
+};
+use iced::{Element, Length, Theme, border, mouse};
+
+use crate::Message;
+use crate::file_tree::TreeEntry;
+use crate::states::{PendingTreeAction, TreeAction, Workspace};
+
+/// Indentation per nesting level, in pixels.
+const INDENT: f32 = 16.0;
+
+/// The name field of a pending tree action, focused when the action starts.
+pub static TREE_ACTION_INPUT_ID: LazyLock<text_input::Id> =
+    LazyLock::new(|| text_input::Id::new("tree-action-name"));
+
+/// Actions on an existing entry, in the order the toolbar and context menu show them.
+const ENTRY_ACTIONS: [TreeAction; 3] = [
+    TreeAction::Rename,
+    TreeAction::Duplicate,
+    TreeAction::Delete,
+];
+
+/// Actions creating an entry.
+const CREATE_ACTIONS: [TreeAction; 3] = [
+    TreeAction::NewPacket,
+    TreeAction::NewProcedure,
+    TreeAction::NewFolder,
+];
+
+pub fn file_tree_panel(workspace: &Workspace) -> Element<'_, Message> {
+    let has_cursor = workspace.tree_cursor.is_some();
+    let create = row(CREATE_ACTIONS
+        .map(|action| tree_button(action.to_string(), Some(Message::StartTreeAction(action)))))
+    .spacing(6);
+    let edit = row(ENTRY_ACTIONS.map(|action| {
+        tree_button(
+            action.to_string(),
+            has_cursor.then_some(Message::StartTreeAction(action)),
+        )
+    }))
+    .spacing(6);
+    let view = row![
+        tree_button(
+            "Expand all".to_string(),
+            Some(Message::ExpandAllDirectories)
+        ),
+        tree_button(
+            "Collapse all".to_string(),
+            Some(Message::CollapseAllDirectories)
+        )
+    ]
+    .spacing(6);
+    let show_all = checkbox("Show all files", workspace.project.manifest.show_all_files)
+        .text_size(12)
+        .size(14)
+        .on_toggle(Message::ShowAllFilesToggled);
+    let mut controls = column![create, edit, view, show_all].spacing(4);
+    if let Some(pending) = &workspace.tree_action {
+        controls = controls.push(action_prompt(pending));
+    }
+
+    let entries = workspace.visible_tree_entries();
+    let rows: Element<Message> = if entries.is_empty() {
+        text("No files yet").size(13).style(text::secondary).into()
+    } else {
+        column(entries.iter().map(|entry| tree_row(workspace, entry)))
+            .spacing(2)
+            .width(Length::Fill)
+            .into()
+    };
+
+    if let Some(hint) = drop_hint(workspace) {
+        controls = controls.push(text(hint).size(13).style(text::primary));
+    }
+
+    // Releasing a dragged entry below the rows moves it to the project root
+    let tree_area = mouse_area(scrollable(rows).height(Length::Fill))
+        .on_release(Message::TreeReleased(String::new()));
+
+    container(column![controls, tree_area].spacing(8).width(Length::Fill))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(5)
+        .into()
+}
+
+fn tree_row<'a>(workspace: &Workspace, entry: &TreeEntry) -> Element<'a, Message> {
+    let label = if entry.is_directory {
+        let marker = if workspace.layout.expanded_dirs.contains(&entry.key) {
+            "▾"
+        } else {
+            "▸"
+        };
+        format!("{marker} {}/", entry.name())
+    } else {
+        format!("  {}", entry.name())
+    };
+
+    let opened = workspace
+        .opened_file
+        .as_ref()
+        .is_some_and(|opened| opened.key == entry.key);
+    let under_cursor = workspace.tree_cursor.as_ref() == Some(&entry.key);
+    let drop_target = workspace
+        .tree_drag
+        .as_ref()
+        .is_some_and(|drag| drag.over.as_ref() == Some(&entry.key));
+    let highlight = if drop_target {
+        RowHighlight::DropTarget
+    } else if opened {
+        RowHighlight::Opened
+    } else if under_cursor {
+        RowHighlight::Cursor
+    } else {
+        RowHighlight::None
+    };
+
+    // Rows take the raw press and release so an entry can be dragged onto another one;
+    // releasing on the row that was pressed is a click
+    let mut row_area = mouse_area(
+        container(text(label).size(14))
+            .padding([2, 5])
+            .width(Length::Fill)
+            .style(move |theme| row_style(theme, highlight)),
+    )
+    .interaction(mouse::Interaction::Pointer)
+    .on_press(Message::TreePressed(entry.key.clone()))
+    .on_release(Message::TreeReleased(entry.key.clone()))
+    .on_right_press(Message::OpenTreeMenu(entry.key.clone()));
+    if workspace.tree_drag.is_some() {
+        row_area = row_area.on_enter(Message::TreeDragOver(entry.key.clone()));
+    }
+
+    let mut entry_view = column![row_area];
+    if workspace.tree_menu.as_ref() == Some(&entry.key) {
+        entry_view = entry_view.push(context_menu(entry));
+    }
+
+    container(entry_view)
+        .padding(iced::Padding::ZERO.left(entry.depth as f32 * INDENT))
+        .into()
+}
+
+#[derive(Clone, Copy)]
+enum RowHighlight {
+    None,
+    Cursor,
+    Opened,
+    DropTarget,
+}
+
+fn row_style(theme: &Theme, highlight: RowHighlight) -> container::Style {
+    let palette = theme.extended_palette();
+    let pair = match highlight {
+        RowHighlight::None => return container::Style::default(),
+        RowHighlight::Cursor => palette.secondary.base,
+        RowHighlight::Opened => palette.primary.base,
+        RowHighlight::DropTarget => palette.primary.weak,
+    };
+    container::Style::default()
+        .background(pair.color)
+        .color(pair.text)
+        .border(border::rounded(2))
+}
+
+/// What a drop will do, while something is being dragged.
+fn drop_hint(workspace: &Workspace) -> Option<String> {
+    let folder = |key: &str| {
+        if key.is_empty() {
+            "the project root".to_string()
+        } else {
+            format!("{key}/")
+        }
+    };
+    if workspace.files_hovering {
+        return Some(format!(
+            "Drop to copy into {}",
+            folder(&workspace.creation_dir())
+        ));
+    }
+    let drag = workspace.tree_drag.as_ref()?;
+    drag.over.as_ref()?;
+    let name = drag.source.rsplit('/').next().unwrap_or_default();
+    Some(format!(
+        "Moving {name}: release on a folder, or below the list for the root"
+    ))
+}
+
+/// The right-click menu shown under an entry.
+fn context_menu<'a>(entry: &TreeEntry) -> Element<'a, Message> {
+    let creates = if entry.is_directory {
+        &CREATE_ACTIONS[..]
+    } else {
+        &[]
+    };
+    let items = creates.iter().chain(&ENTRY_ACTIONS).map(|&action| {
+        button(text(action.to_string()).size(13))
+            .padding([2, 8])
+            .width(Length::Fill)
+            .style(button::text)
+            .on_press(Message::StartTreeAction(action))
+            .into()
+    });
+    let close = button(text("Close").size(13))
+        .padding([2, 8])
+        .width(Length::Fill)
+        .style(button::text)
+        .on_press(Message::CloseTreeMenu);
+
+    container(column(items).push(close).width(160))
+        .padding(4)
+        .style(container::bordered_box)
+        .into()
+}
+
+/// Name input or delete confirmation for the pending action.
+fn action_prompt(pending: &PendingTreeAction) -> Element<'_, Message> {
+    let confirm_label = if pending.action == TreeAction::Delete {
+        "Move to trash"
+    } else {
+        "OK"
+    };
+    let ready = pending.validation_error.is_none() && !pending.name_input.is_empty();
+    let buttons = row![
+        tree_button("Cancel".to_string(), Some(Message::CancelTreeAction)),
+        tree_button(
+            confirm_label.to_string(),
+            ready.then_some(Message::ConfirmTreeAction)
+        )
+    ]
+    .spacing(6);
+
+    let mut prompt = column![text(pending.action.to_string()).size(13)].spacing(4);
+    prompt = if pending.action == TreeAction::Delete {
+        prompt.push(
+            text(format!(
+                "Move '{}' to the project trash?",
+                pending.name_input
+            ))
+            .size(13),
+        )
+    } else {
+        let mut input = text_input("Name", &pending.name_input)
+            .id(TREE_ACTION_INPUT_ID.clone())
+            .size(13)
+            .padding(4)
+            .on_input(Message::TreeActionNameChanged);
+        if ready {
+            input = input.on_submit(Message::ConfirmTreeAction);
+        }
+        prompt.push(input)
+    };
+    if let Some(error) = &pending.validation_error {
+        prompt = prompt.push(text(error).size(12).style(text::danger));
+    }
+
+    container(prompt.push(buttons))
+        .padding(6)
+        .width(Length::Fill)
+        .style(container::bordered_box)
+        .into()
+}
+
+fn tree_button<'a>(label: String, message: Option<Message>) -> Element<'a, Message> {
+    button(text(label).size(12))
+        .padding([2, 8])
+        .style(button::secondary)
+        .on_press_maybe(message)
+        .into()
+}
@@ -1,16 +1,24 @@
 use iced::{
-    Element,
-    widget::{button, column, container, row},
+    Element, Length,
+    widget::{button, column, container, pick_list, row, text},
 };
 
 use crate::{Dispatcher, Message};
 
-pub mod project;
 pub mod active_project;
+pub mod bundle;
+pub mod error_banner;
+pub mod file_tree;
+pub mod format;
+pub mod project;
+pub mod project_actions;
+pub mod project_settings;
+pub mod quick_open;
 pub mod resizable_panel;
 pub mod resizable_split;
+pub mod search;
 
-pub fn on_boarding(_state: &Dispatcher) -> Element<Message> {
+pub fn on_boarding(_state: &Dispatcher) -> Element<'_, Message> {
     container(column![
         row![
             "Welcome to Tnet-Dispatcher First onboarding dialog",
@@ -22,7 +30,7 @@ pub fn on_boarding(_state: &Dispatcher) -> Element<Message> {
     .into()
 }
 
-pub fn on_boarding_2(_state: &Dispatcher) -> Element<Message> {
+pub fn on_boarding_2(_state: &Dispatcher) -> Element<'_, Message> {
     container(column![
         row![
             "Welcome to Tnet-Dispatcher Second onboarding dialog",
@@ -35,18 +43,73 @@ pub fn on_boarding_2(_state: &Dispatcher) -> Element<Message> {
     .into()
 }
 
-pub fn no_project_selected(state: &Dispatcher) -> Element<Message> {
+pub fn no_project_selected(state: &Dispatcher) -> Element<'_, Message> {
     project::no_open_project(state)
 }
 
-pub fn creating_project(state: &Dispatcher) -> Element<Message> {
+pub fn creating_project(state: &Dispatcher) -> Element<'_, Message> {
     project::create_new_project_dialog(state)
 }
 
-pub fn project_selected(state: &Dispatcher) -> Element<Message> {
-    project::opened_project(state)
+pub fn project_selected(state: &Dispatcher) -> Element<'_, Message> {
+    match state.states.tabs.active() {
+        Some(workspace) => project::opened_project(state, workspace),
+        None => no_project_selected(state),
+    }
+}
+
+pub fn editing_project_settings(state: &Dispatcher) -> Element<'_, Message> {
+    match state.states.tabs.active() {
+        Some(workspace) => project_settings::project_settings_dialog(state, workspace),
+        None => no_project_selected(state),
+    }
 }
 
-pub fn selecting_existing_project(state: &Dispatcher) -> Element<Message> {
+pub fn exporting_project(state: &Dispatcher) -> Element<'_, Message> {
+    match state.states.tabs.active() {
+        Some(workspace) => bundle::export_project_dialog(state, workspace),
+        None => no_project_selected(state),
+    }
+}
+
+pub fn importing_project(state: &Dispatcher) -> Element<'_, Message> {
+    bundle::import_project_dialog(state)
+}
+
+pub fn selecting_existing_project(state: &Dispatcher) -> Element<'_, Message> {
     project::select_existing_project(state)
 }
+
+/// Places the error banner above `content` when there are errors to show.
+pub fn with_error_banner<'a>(
+    state: &'a Dispatcher,
+    content: Element<'a, Message>,
+) -> Element<'a, Message> {
+    match error_banner::error_banner(&state.states.errors) {
+        Some(banner) => column![container(banner).padding(8), content]
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into(),
+        None => content,
+    }
+}
+
+/// Drop-down listing built-in and custom themes, plus a button to rescan the themes folder.
+pub fn theme_picker(state: &Dispatcher) -> Element<'_, Message> {
+    let themes = &state.states.themes;
+    row![
+        pick_list(
+            themes.available.as_slice(),
+            Some(&themes.active),
+            Message::ThemeSelected
+        )
+        .text_size(13)
+        .padding([4, 10]),
+        button(text("Reload").size(13))
+            .padding([4, 8])
+            .style(button::secondary)
+            .on_press(Message::ReloadThemes)
+    ]
+    .spacing(4)
+    .into()
+}
@@ -1,12 +1,26 @@
-use iced::widget::{button, column, container, horizontal_rule, row, text, text_input, scrollable};
-use iced::{Color, Element, Fill, Length};
+use std::sync::LazyLock;
 
+use iced::widget::{
+    button, column, container, horizontal_rule, pick_list, row, scrollable, stack, text, text_input,
+};
+use iced::{Background, Element, Fill, Length, Theme};
+
+use crate::config::RecentProject;
+use crate::project::ProjectSummary;
+use crate::states::{ProjectSort, Workspace};
 use crate::{Dispatcher, Message};
 
-use super::active_project;
 use super::resizable_split::{horizontal, vertical};
+use super::{active_project, file_tree, format, project_actions, search};
+
+/// Focus target of the project picker's filter box.
+pub static PROJECT_FILTER_ID: LazyLock<text_input::Id> =
+    LazyLock::new(|| text_input::Id::new("project-filter"));
+
+/// How many recent projects the welcome screen lists.
+const RECENT_PROJECTS_SHOWN: usize = 8;
 
-pub fn no_open_project(_state: &Dispatcher) -> Element<Message> {
+pub fn no_open_project(state: &Dispatcher) -> Element<'_, Message> {
     // Title section
     let title = text("Welcome to Tnet-Dispatcher").size(36); // Size in pixels
 
@@ -23,13 +37,44 @@ pub fn no_open_project(_state: &Dispatcher) -> Element<Message> {
         .padding(10)
         .on_press(Message::OpenExistingProject);
 
+    let import_project = button(row![text("Import Project")].spacing(10))
+        .width(200)
+        .padding(10)
+        .on_press(Message::OpenImportProject);
+
     // Container for the buttons
-    let actions = row![new_project, open_project].spacing(20).padding(20);
+    let mut actions = row![new_project, open_project, import_project];
+    if !state.states.tabs.open.is_empty() {
+        actions = actions.push(
+            button(row![text("Back to open projects")].spacing(10))
+                .width(200)
+                .padding(10)
+                .style(button::secondary)
+                .on_press(Message::ShowWorkspace),
+        );
+    }
+    let actions = actions
+        .push(super::theme_picker(state))
+        .spacing(20)
+        .padding(20)
+        .align_y(iced::Alignment::Center);
 
-    // Recent projects would go here
     let recent_title = text("Recent Projects").size(20);
 
-    let empty_message = text("No recent projects").size(14);
+    let recent_projects = state.conf.sorted_recent_projects();
+    let recent_list: Element<Message> = if recent_projects.is_empty() {
+        text("No recent projects").size(14).into()
+    } else {
+        column(
+            recent_projects
+                .into_iter()
+                .take(RECENT_PROJECTS_SHOWN)
+                .map(recent_project_row),
+        )
+        .spacing(5)
+        .width(Length::Fixed(520.0))
+        .into()
+    };
 
     // Main content layout
     container(
@@ -40,7 +85,7 @@ pub fn no_open_project(_state: &Dispatcher) -> Element<Message> {
             actions,
             horizontal_rule(10),
             recent_title,
-            empty_message
+            recent_list
         ]
         .spacing(20)
         .padding(20),
@@ -52,7 +97,32 @@ pub fn no_open_project(_state: &Dispatcher) -> Element<Message> {
     .into()
 }
 
-pub fn create_new_project_dialog(state: &Dispatcher) -> Element<Message> {
+fn recent_project_row(recent: &RecentProject) -> Element<'_, Message> {
+    let open = button(
+        row![
+            text(&recent.name).width(Length::Fill),
+            text(format::relative_time(recent.last_opened)).size(12)
+        ]
+        .spacing(10),
+    )
+    .padding(8)
+    .width(Length::Fill)
+    .on_press(Message::OpenRecentProject(recent.name.clone()));
+
+    let pin = button(text(if recent.pinned { "Unpin" } else { "Pin" }).size(12))
+        .padding(8)
+        .style(button::secondary)
+        .on_press(Message::TogglePinnedRecentProject(recent.name.clone()));
+
+    let remove = button(text("Remove").size(12))
+        .padding(8)
+        .style(button::secondary)
+        .on_press(Message::RemoveRecentProject(recent.name.clone()));
+
+    row![open, pin, remove].spacing(5).into()
+}
+
+pub fn create_new_project_dialog(state: &Dispatcher) -> Element<'_, Message> {
     let title = text("Create New Project").size(24);
 
     let description = text("Enter a name for your new project:").size(16);
@@ -62,8 +132,23 @@ pub fn create_new_project_dialog(state: &Dispatcher) -> Element<Message> {
         .width(Length::Fill)
         .on_input(Message::NewProjectNameChanged);
 
+    let template_picker = column![
+        text("Template").size(14),
+        pick_list(
+            state.states.new_project.templates.as_slice(),
+            Some(&state.states.new_project.template),
+            Message::NewProjectTemplateSelected
+        )
+        .padding(10)
+        .width(Length::Fill),
+        text(state.states.new_project.template.summary())
+            .size(12)
+            .style(text::secondary)
+    ]
+    .spacing(5);
+
     let validation_message = if let Some(error) = &state.states.new_project.validation_error {
-        text(error).size(14).color(Color::from_rgb(0.9, 0.2, 0.2))
+        text(error).size(14).style(text::danger)
     } else {
         // Use a non-breaking space to maintain consistent height without visible text
         text("\u{00A0}").size(14)
@@ -88,12 +173,24 @@ pub fn create_new_project_dialog(state: &Dispatcher) -> Element<Message> {
         .width(Length::Fill)
         .padding(10);
 
+    // Offer the colliding project instead of making the user retype its name
+    let open_existing: Element<Message> = match &state.states.new_project.existing_match {
+        Some(existing) => button(text(format!("Open '{existing}' instead")))
+            .padding(10)
+            .style(button::secondary)
+            .on_press(Message::OpenCollidingProject)
+            .into(),
+        None => column![].into(),
+    };
+
     container(
         column![
             title,
             description,
             project_name_input,
             validation_message,
+            template_picker,
+            open_existing,
             button_row
         ]
         .spacing(20)
@@ -102,70 +199,65 @@ pub fn create_new_project_dialog(state: &Dispatcher) -> Element<Message> {
     )
     .width(Length::Fixed(400.00))
     .center_x(Length::Fixed(400.00))
-    .center_y(Length::Fixed(400.00))
+    .center_y(Length::Fixed(480.00))
     .into()
 }
 
-pub fn select_existing_project(state: &Dispatcher) -> Element<Message> {
+pub fn select_existing_project(state: &Dispatcher) -> Element<'_, Message> {
     let title = text("Select Existing Project").size(24);
-    
+
     let description = text("Choose a project from the list:").size(16);
-    
-    // Create the dropdown header that shows the currently selected project or a placeholder
-    let dropdown_text = match &state.states.existing_project.selected_project {
-        Some(project) => project.clone(),
-        None => "Select a project...".to_string(),
-    };
-    
-    let dropdown_header = button(
-        row![
-            text(dropdown_text).width(Length::Fill),
-            text(if state.states.existing_project.is_dropdown_open { "▲" } else { "▼" })
-        ].spacing(10).width(Length::Fill)
+
+    let picker = &state.states.existing_project;
+
+    // Type-to-filter box; arrow keys and Enter are handled by the keyboard subscription
+    let filter_row = row![
+        text_input("Filter projects...", &picker.query)
+            .id(PROJECT_FILTER_ID.clone())
+            .padding(10)
+            .width(Length::Fill)
+            .on_input(Message::ProjectFilterChanged)
+            .on_submit(Message::SubmitProjectPicker),
+        pick_list(
+            ProjectSort::ALL,
+            Some(picker.sort),
+            Message::ProjectSortChanged
+        )
+        .padding(10)
+    ]
+    .spacing(10);
+
+    let visible = picker.visible_projects(&state.conf.recent_projects);
+    let no_matches = visible.is_empty() && !picker.available_projects.is_empty();
+    let project_list = column(
+        visible
+            .into_iter()
+            .map(|summary| project_picker_row(state, summary)),
     )
-    .padding(10)
-    .width(Length::Fill)
-    .on_press(Message::ToggleProjectDropdown);
-    
-    // Create the dropdown list
-    let mut dropdown_list = column![];
-    
-    if state.states.existing_project.is_dropdown_open {
-        for project in &state.states.existing_project.available_projects {
-            let project_row = button(text(project).width(Length::Fill))
-                .padding(10)
-                .width(Length::Fill)
-                .on_press(Message::SelectExistingProject(project.clone()));
-            
-            dropdown_list = dropdown_list.push(project_row);
-        }
-    }
-    
-    // Wrap the dropdown list in a scrollable for better UX with many projects
-    let scrollable_dropdown = scrollable(dropdown_list)
-        .height(Length::Fixed(
-            if state.states.existing_project.is_dropdown_open && !state.states.existing_project.available_projects.is_empty() {
-                // Limit height based on number of projects (max 200px)
-                (state.states.existing_project.available_projects.len() as f32 * 40.0).min(200.0)
-            } else {
-                0.0
-            }
-        ));
-    
+    .spacing(2);
+
+    // Limit height based on number of projects (max 280px)
+    let list_height = (picker.available_projects.len() as f32 * 56.0).min(280.0);
+    let scrollable_list = scrollable(project_list).height(Length::Fixed(list_height));
+
     // Empty state message when no projects exist
     let empty_message = if state.states.existing_project.available_projects.is_empty() {
         text("No existing projects found. Create a new project first.")
             .size(14)
-            .color(Color::from_rgb(0.7, 0.7, 0.7))
+            .style(text::secondary)
+    } else if no_matches {
+        text(format!("No projects match '{}'", picker.query))
+            .size(14)
+            .style(text::secondary)
     } else {
         text("").height(0.0)
     };
-    
+
     // Action buttons
     let cancel_button = button(text("Cancel"))
         .padding(10)
         .on_press(Message::CancelProjectSelection);
-    
+
     let open_button = if state.states.existing_project.selected_project.is_none() {
         button(text("Open")).padding(10)
     } else {
@@ -173,38 +265,94 @@ pub fn select_existing_project(state: &Dispatcher) -> Element<Message> {
             .padding(10)
             .on_press(Message::ConfirmSelectedProject)
     };
-    
+
     let button_row = row![cancel_button, open_button]
         .spacing(10)
         .width(Length::Fill)
         .padding(10);
-    
+
+    // Link a project folder kept outside the data directory and open it in place
+    let folder_path = &state.states.existing_project.folder_path;
+    let open_folder = column![
+        text("Or open a folder from anywhere:").size(14),
+        row![
+            text_input("/path/to/project", folder_path)
+                .padding(10)
+                .width(Length::Fill)
+                .on_input(Message::FolderPathChanged)
+                .on_submit(Message::OpenFolder),
+            button(text("Open folder"))
+                .padding(10)
+                .on_press_maybe((!folder_path.trim().is_empty()).then_some(Message::OpenFolder))
+        ]
+        .spacing(10)
+    ]
+    .spacing(5);
+
     // Main container
     container(
         column![
             title,
             description,
-            dropdown_header,
-            scrollable_dropdown,
+            filter_row,
+            scrollable_list,
             empty_message,
+            project_actions::project_actions_section(state),
+            open_folder,
             button_row
         ]
         .spacing(20)
         .padding(20)
         .width(Length::Fill),
     )
-    .width(Length::Fixed(400.00))
-    .center_x(Length::Fixed(400.00))
-    .center_y(Length::Fixed(400.00))
+    .width(Length::Fixed(520.00))
+    .center_x(Length::Fixed(520.00))
     .into()
 }
 
-pub fn opened_project(state: &Dispatcher) -> Element<Message> {
-    use iced::Background;
-    
-    // Get the layout ratios from state
-    let h_ratio = state.states.layout.horizontal_ratio;
-    let v_ratio = state.states.layout.vertical_ratio;
+/// One picker entry: name and description on the left, last modification on the right.
+fn project_picker_row<'a>(
+    state: &'a Dispatcher,
+    summary: &'a ProjectSummary,
+) -> Element<'a, Message> {
+    let name = if state.conf.linked_project(&summary.name).is_some() {
+        format!("{} (linked)", summary.name)
+    } else {
+        summary.name.clone()
+    };
+    let modified = summary
+        .modified
+        .map(|modified| format!("modified {}", format::relative_time(modified)))
+        .unwrap_or_default();
+
+    let selected = state.states.existing_project.selected_project.as_ref() == Some(&summary.name);
+    button(
+        row![
+            column![
+                text(name),
+                text(&summary.description).size(12).style(text::secondary)
+            ]
+            .width(Length::Fill),
+            text(modified).size(12)
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center),
+    )
+    .padding(8)
+    .width(Length::Fill)
+    .style(if selected {
+        button::primary
+    } else {
+        button::text
+    })
+    .on_press(Message::SelectExistingProject(summary.name.clone()))
+    .into()
+}
+
+pub fn opened_project<'a>(state: &'a Dispatcher, workspace: &'a Workspace) -> Element<'a, Message> {
+    // Get the layout ratios from the workspace
+    let h_ratio = workspace.layout.horizontal_ratio;
+    let v_ratio = workspace.layout.vertical_ratio;
 
     // Main view panel with header
     let main_panel = container(
@@ -212,27 +360,15 @@ pub fn opened_project(state: &Dispatcher) -> Element<Message> {
             container(text("Main View").size(16))
                 .padding(10)
                 .width(Length::Fill)
-                .style(|_: &_| container::Style {
-                    text_color: Some(Color::WHITE),
-                    background: Some(Background::Color(Color::from_rgb(0.2, 0.2, 0.3))),
-                    ..Default::default()
-                }),
-            container(active_project::main_view_panel(state))
+                .style(panel_header_style),
+            container(active_project::main_view_panel(workspace))
                 .padding(5)
                 .width(Length::Fill)
                 .height(Length::Fill)
         ]
-        .spacing(1)
+        .spacing(1),
     )
-    .style(|_: &_| container::Style {
-        background: Some(Background::Color(Color::from_rgb(0.18, 0.18, 0.23))),
-        border: iced::Border {
-            color: Color::from_rgb(0.3, 0.3, 0.4),
-            width: 1.0,
-            radius: 3.0.into(),
-        },
-        ..Default::default()
-    })
+    .style(panel_style)
     .width(Length::Fill)
     .height(Length::Fill);
 
@@ -242,84 +378,211 @@ pub fn opened_project(state: &Dispatcher) -> Element<Message> {
             container(text("Inspector").size(16))
                 .padding(10)
                 .width(Length::Fill)
-                .style(|_: &_| container::Style {
-                    text_color: Some(Color::WHITE),
-                    background: Some(Background::Color(Color::from_rgb(0.2, 0.2, 0.3))),
-                    ..Default::default()
-                }),
-            container(active_project::inspector_panel(state))
+                .style(panel_header_style),
+            container(active_project::inspector_panel(workspace))
                 .padding(5)
                 .width(Length::Fill)
                 .height(Length::Fill)
         ]
-        .spacing(1)
+        .spacing(1),
     )
-    .style(|_: &_| container::Style {
-        background: Some(Background::Color(Color::from_rgb(0.18, 0.18, 0.23))),
-        border: iced::Border {
-            color: Color::from_rgb(0.3, 0.3, 0.4),
-            width: 1.0,
-            radius: 3.0.into(),
-        },
-        ..Default::default()
-    })
+    .style(panel_style)
     .width(Length::Fill)
     .height(Length::Fill);
 
-    // File tree panel with header
+    // File tree panel with header, switching to the search panel
+    let side_tab = |label, active: bool, show_search| {
+        button(text(label).size(16))
+            .padding([0, 8])
+            .style(if active {
+                button::text
+            } else {
+                panel_tab_style
+            })
+            .on_press(Message::ShowSearchPanel(show_search))
+    };
+    let side_content = if workspace.showing_search {
+        search::search_panel(workspace)
+    } else {
+        file_tree::file_tree_panel(workspace)
+    };
     let file_tree_panel = container(
         column![
-            container(text("Files").size(16))
-                .padding(10)
-                .width(Length::Fill)
-                .style(|_: &_| container::Style {
-                    text_color: Some(Color::WHITE),
-                    background: Some(Background::Color(Color::from_rgb(0.2, 0.2, 0.3))),
-                    ..Default::default()
-                }),
-            container(active_project::file_tree_panel(state))
+            container(row![
+                side_tab("Files", !workspace.showing_search, false),
+                side_tab("Search", workspace.showing_search, true)
+            ])
+            .padding(10)
+            .width(Length::Fill)
+            .style(panel_header_style),
+            container(side_content)
                 .padding(5)
                 .width(Length::Fill)
                 .height(Length::Fill)
         ]
-        .spacing(1)
+        .spacing(1),
     )
-    .style(|_: &_| container::Style {
-        background: Some(Background::Color(Color::from_rgb(0.18, 0.18, 0.23))),
-        border: iced::Border {
-            color: Color::from_rgb(0.3, 0.3, 0.4),
-            width: 1.0,
-            radius: 3.0.into(),
-        },
-        ..Default::default()
-    })
+    .style(panel_style)
     .width(Length::Fill)
     .height(Length::Fill);
 
     // First, create a vertical split between main panel and file tree (side by side)
-    let vertical_split = vertical(
-        main_panel,
-        file_tree_panel,
-        h_ratio,
-        Message::ResizeHorizontal
-    );
+    let top: Element<Message> = if workspace.layout.file_tree_collapsed {
+        main_panel.into()
+    } else {
+        vertical(
+            main_panel,
+            file_tree_panel,
+            h_ratio,
+            Message::ResizeHorizontal,
+        )
+    };
 
     // Then create a horizontal split between the vertical split and inspector (top/bottom)
-    let layout = horizontal(
-        vertical_split,
-        inspector_panel,
-        v_ratio,
-        Message::ResizeVertical
-    );
+    let layout = if workspace.layout.inspector_collapsed {
+        top
+    } else {
+        horizontal(top, inspector_panel, v_ratio, Message::ResizeVertical)
+    };
+
+    // Toolbar with the project name and project-level actions
+    let manifest = &workspace.project.manifest;
+    let toolbar = row![
+        text(&manifest.name).size(18),
+        text(&manifest.description)
+            .size(13)
+            .style(text::secondary)
+            .width(Length::Fill),
+        panel_toggle(
+            "Files",
+            workspace.layout.file_tree_collapsed,
+            Message::ToggleFileTree
+        ),
+        panel_toggle(
+            "Inspector",
+            workspace.layout.inspector_collapsed,
+            Message::ToggleInspector
+        ),
+        button(text("Reset layout").size(13))
+            .padding([4, 10])
+            .style(button::secondary)
+            .on_press(Message::ResetLayout),
+        super::theme_picker(state),
+        button(text("Export").size(13))
+            .padding([4, 10])
+            .style(button::secondary)
+            .on_press(Message::OpenExportProject),
+        button(text("Settings").size(13))
+            .padding([4, 10])
+            .on_press(Message::OpenProjectSettings)
+    ]
+    .spacing(12)
+    .align_y(iced::Alignment::Center);
 
     // Main container
-    container(layout)
+    let content = container(column![tab_bar(state), toolbar, layout].spacing(8))
         .width(Length::Fill)
         .height(Length::Fill)
         .padding(8)
-        .style(|_: &_| container::Style {
-            background: Some(Background::Color(Color::from_rgb(0.15, 0.15, 0.2))),
-            ..Default::default()
+        .style(workspace_style);
+
+    match &workspace.quick_open {
+        Some(quick_open) => stack![
+            content,
+            super::quick_open::quick_open_overlay(workspace, quick_open)
+        ]
+        .into(),
+        None => content.into(),
+    }
+}
+
+/// One tab per open project, plus a button to open another one.
+fn tab_bar(state: &Dispatcher) -> Element<'_, Message> {
+    let tabs = &state.states.tabs;
+    let mut bar = row![].spacing(4).align_y(iced::Alignment::Center);
+
+    for (index, workspace) in tabs.open.iter().enumerate() {
+        let active = index == tabs.active;
+        bar = bar.push(
+            row![
+                button(text(&workspace.project.current_project).size(13))
+                    .padding([4, 10])
+                    .style(if active {
+                        button::primary
+                    } else {
+                        button::secondary
+                    })
+                    .on_press(Message::SelectTab(workspace.id)),
+                button(text("×").size(13))
+                    .padding([4, 6])
+                    .style(button::text)
+                    .on_press(Message::CloseTab(workspace.id))
+            ]
+            .align_y(iced::Alignment::Center),
+        );
+    }
+
+    bar.push(
+        button(text("+").size(13))
+            .padding([4, 10])
+            .style(button::secondary)
+            .on_press(Message::NewTab),
+    )
+    .into()
+}
+
+/// Background behind all panels, taken from the active theme.
+fn workspace_style(theme: &Theme) -> container::Style {
+    let palette = theme.extended_palette();
+    container::Style {
+        text_color: Some(palette.background.base.text),
+        background: Some(Background::Color(palette.background.base.color)),
+        ..Default::default()
+    }
+}
+
+/// A side panel tab that is not showing: dimmed text on the header background.
+fn panel_tab_style(theme: &Theme, status: button::Status) -> button::Style {
+    let style = button::text(theme, status);
+    button::Style {
+        text_color: theme.extended_palette().background.strong.color,
+        ..style
+    }
+}
+
+/// Body and border of a single panel.
+fn panel_style(theme: &Theme) -> container::Style {
+    let palette = theme.extended_palette();
+    container::Style {
+        background: Some(Background::Color(palette.background.weak.color)),
+        border: iced::Border {
+            color: palette.background.strong.color,
+            width: 1.0,
+            radius: 3.0.into(),
+        },
+        ..Default::default()
+    }
+}
+
+/// Title bar at the top of a panel.
+fn panel_header_style(theme: &Theme) -> container::Style {
+    let palette = theme.extended_palette();
+    container::Style {
+        text_color: Some(palette.background.strong.text),
+        background: Some(Background::Color(palette.background.strong.color)),
+        ..Default::default()
+    }
+}
+
+/// Toolbar button showing or hiding a panel; styled as secondary while the panel is hidden.
+fn panel_toggle(label: &str, collapsed: bool, on_press: Message) -> Element<'_, Message> {
+    button(text(label).size(13))
+        .padding([4, 10])
+        .style(if collapsed {
+            button::secondary
+        } else {
+            button::primary
         })
+        .on_press(on_press)
         .into()
 }
@@ -0,0 +1,168 @@
+use iced::widget::{button, column, row, text, text_input};
+use iced::{Element, Length};
+
+use crate::states::ProjectAction;
+use crate::{Dispatcher, Message};
+
+/// Rename/duplicate/delete/archive controls shown under the project picker.
+pub fn project_actions_section(state: &Dispatcher) -> Element<'_, Message> {
+    let actions = &state.states.project_actions;
+    let mut section = column![].spacing(10);
+
+    if let Some(trashed) = &actions.last_trashed {
+        section = section.push(
+            row![
+                text(format!("Deleted '{}'", trashed.name))
+                    .size(14)
+                    .width(Length::Fill),
+                button(text("Undo").size(13))
+                    .padding([4, 10])
+                    .on_press(Message::UndoDeleteProject)
+            ]
+            .spacing(10)
+            .align_y(iced::Alignment::Center),
+        );
+    }
+
+    if let Some(selected) = &state.states.existing_project.selected_project {
+        if let Some(linked) = state.conf.linked_project(selected) {
+            // Linked folders belong to the user; only the link itself is managed here
+            section = section.push(
+                row![
+                    text(format!("Linked from {}", linked.path.display()))
+                        .size(13)
+                        .width(Length::Fill),
+                    button(text("Unlink").size(13))
+                        .padding([4, 10])
+                        .style(button::secondary)
+                        .on_press(Message::UnlinkProject)
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+            );
+        } else if let Some(action) = actions.pending {
+            section = section.push(name_prompt(state, action));
+        } else if actions.confirming_delete {
+            section = section.push(
+                column![
+                    text(format!(
+                        "Delete '{selected}'? It will be moved to the trash and can be restored with Undo."
+                    ))
+                    .size(14),
+                    row![
+                        button(text("Cancel").size(13))
+                            .padding([4, 10])
+                            .style(button::secondary)
+                            .on_press(Message::CancelProjectAction),
+                        button(text("Delete").size(13))
+                            .padding([4, 10])
+                            .style(button::danger)
+                            .on_press(Message::ConfirmDeleteProject)
+                    ]
+                    .spacing(10)
+                ]
+                .spacing(8),
+            );
+        } else {
+            let action_button = |label, message| {
+                button(text(label).size(13))
+                    .padding([4, 10])
+                    .style(button::secondary)
+                    .on_press(message)
+            };
+            section = section.push(
+                row![
+                    action_button("Rename", Message::StartProjectAction(ProjectAction::Rename)),
+                    action_button(
+                        "Duplicate",
+                        Message::StartProjectAction(ProjectAction::Duplicate)
+                    ),
+                    action_button("Archive", Message::ArchiveProject),
+                    action_button("Delete", Message::RequestDeleteProject),
+                ]
+                .spacing(8),
+            );
+        }
+    }
+
+    if !actions.archived_projects.is_empty() {
+        let label = format!(
+            "{} archived project(s) {}",
+            actions.archived_projects.len(),
+            if actions.show_archived { "▲" } else { "▼" }
+        );
+        section = section.push(
+            button(text(label).size(13))
+                .padding([4, 10])
+                .style(button::text)
+                .on_press(Message::ToggleShowArchived),
+        );
+
+        if actions.show_archived {
+            for name in &actions.archived_projects {
+                section = section.push(
+                    row![
+                        text(name).size(14).width(Length::Fill),
+                        button(text("Unarchive").size(13))
+                            .padding([4, 10])
+                            .style(button::secondary)
+                            .on_press(Message::UnarchiveProject(name.clone()))
+                    ]
+                    .spacing(10)
+                    .align_y(iced::Alignment::Center),
+                );
+            }
+        }
+    }
+
+    section.into()
+}
+
+/// Name input for rename/duplicate, validated like a new project name.
+fn name_prompt(state: &Dispatcher, action: ProjectAction) -> Element<'_, Message> {
+    let actions = &state.states.project_actions;
+    let label = match action {
+        ProjectAction::Rename => "New name:",
+        ProjectAction::Duplicate => "Name of the copy:",
+    };
+
+    let confirm = button(
+        text(match action {
+            ProjectAction::Rename => "Rename",
+            ProjectAction::Duplicate => "Duplicate",
+        })
+        .size(13),
+    )
+    .padding([4, 10]);
+    let confirm = if actions.validation_error.is_none() {
+        confirm.on_press(Message::ConfirmProjectAction)
+    } else {
+        confirm
+    };
+
+    let mut prompt = column![
+        text(label).size(14),
+        text_input("project-name", &actions.name_input)
+            .padding(8)
+            .on_input(Message::ProjectActionNameChanged)
+            .on_submit(Message::ConfirmProjectAction),
+    ]
+    .spacing(8);
+
+    if let Some(error) = &actions.validation_error {
+        prompt = prompt.push(text(error).size(13).style(text::danger));
+    }
+
+    prompt
+        .push(
+            row![
+                button(text("Cancel").size(13))
+                    .padding([4, 10])
+                    .style(button::secondary)
+                    .on_press(Message::CancelProjectAction),
+                confirm
+            ]
+            .spacing(10),
+        )
+        .into()
+}
@@ -0,0 +1,133 @@
+use iced::widget::{button, column, container, row, text, text_input};
+use iced::{Element, Length};
+
+use crate::states::Workspace;
+use crate::{Dispatcher, Message};
+
+use super::format;
+
+pub fn project_settings_dialog<'a>(
+    state: &'a Dispatcher,
+    workspace: &'a Workspace,
+) -> Element<'a, Message> {
+    let settings = &state.states.project_settings;
+    let manifest = &workspace.project.manifest;
+
+    let title = text("Project Settings").size(24);
+    let subtitle = text(format!(
+        "{} (schema v{})",
+        workspace.project.current_project, manifest.schema_version
+    ))
+    .size(14);
+
+    let field = |label: &'static str,
+                 placeholder: &'static str,
+                 value: &str,
+                 on_input: fn(String) -> Message| {
+        column![
+            text(label).size(14),
+            text_input(placeholder, value)
+                .padding(10)
+                .width(Length::Fill)
+                .on_input(on_input)
+        ]
+        .spacing(5)
+    };
+
+    let validation_message = if let Some(error) = &settings.validation_error {
+        text(error).size(14).style(text::danger)
+    } else {
+        // Use a non-breaking space to maintain consistent height without visible text
+        text("\u{00A0}").size(14)
+    };
+
+    let cancel_button = button(text("Cancel"))
+        .padding(10)
+        .on_press(Message::CancelProjectSettings);
+
+    let save_button = button(text("Save"))
+        .padding(10)
+        .on_press(Message::SaveProjectSettings);
+
+    let template_button = button(text(if settings.saved_as_template {
+        "Saved as template"
+    } else if settings.confirm_template_replace {
+        "Replace template"
+    } else {
+        "Save as template"
+    }))
+    .padding(10)
+    .style(button::secondary)
+    .on_press(Message::SaveProjectAsTemplate(
+        settings.confirm_template_replace,
+    ));
+
+    let button_row = row![cancel_button, save_button, template_button]
+        .spacing(10)
+        .width(Length::Fill)
+        .padding(10);
+
+    container(
+        column![
+            title,
+            subtitle,
+            field(
+                "Display name",
+                "My Project",
+                &settings.name,
+                Message::ProjectSettingsNameChanged
+            ),
+            field(
+                "Description",
+                "What this project tests",
+                &settings.description,
+                Message::ProjectSettingsDescriptionChanged
+            ),
+            field(
+                "Tags (comma separated)",
+                "smoke, auth",
+                &settings.tags,
+                Message::ProjectSettingsTagsChanged
+            ),
+            field(
+                "Default server target",
+                "127.0.0.1:8080",
+                &settings.default_target,
+                Message::ProjectSettingsTargetChanged
+            ),
+            field(
+                "File types shown in the tree (comma separated)",
+                "json, proc",
+                &settings.file_types,
+                Message::ProjectSettingsFileTypesChanged
+            ),
+            field(
+                "Hidden from the tree (.gitignore patterns, comma separated)",
+                "build/, *.bin",
+                &settings.ignore_patterns,
+                Message::ProjectSettingsIgnorePatternsChanged
+            ),
+            text(format!(
+                "Created {} · Modified {}",
+                format::date_time(manifest.created),
+                format::date_time(manifest.modified)
+            ))
+            .size(12),
+            validation_message,
+        ]
+        .push_maybe(settings.confirm_template_replace.then(|| {
+            text(format!(
+                "A template named '{}' already exists. Press \"Replace template\" to overwrite it.",
+                workspace.project.current_project
+            ))
+            .size(14)
+        }))
+        .push(button_row)
+        .spacing(15)
+        .padding(20)
+        .width(Length::Fill),
+    )
+    .width(Length::Fixed(500.00))
+    .center_x(Length::Fixed(500.00))
+    .into()
+}
@@ -0,0 +1,110 @@
+use std::sync::LazyLock;
+
+use iced::widget::{button, center, column, container, opaque, row, scrollable, text, text_input};
+use iced::{Color, Element, Font, Length, Theme};
+
+use crate::Message;
+use crate::states::{QuickOpenState, Workspace};
+
+/// The finder's query field, focused when it opens.
+pub static QUICK_OPEN_INPUT_ID: LazyLock<text_input::Id> =
+    LazyLock::new(|| text_input::Id::new("quick-open"));
+
+/// The Ctrl+P file finder, drawn over the workspace.
+pub fn quick_open_overlay<'a>(
+    workspace: &'a Workspace,
+    quick_open: &'a QuickOpenState,
+) -> Element<'a, Message> {
+    let input = text_input("Go to file…", &quick_open.query)
+        .id(QUICK_OPEN_INPUT_ID.clone())
+        .padding(8)
+        .on_input(Message::QuickOpenQueryChanged)
+        .on_submit(Message::SubmitQuickOpen);
+
+    let matches = quick_open.matches(&workspace.recent_files);
+    let results: Element<Message> = if matches.is_empty() {
+        text("No matching files")
+            .size(13)
+            .style(text::secondary)
+            .into()
+    } else {
+        column(matches.into_iter().enumerate().map(|(index, entry)| {
+            let folder = entry
+                .key
+                .rsplit_once('/')
+                .map(|(folder, _)| folder)
+                .unwrap_or_default();
+            button(
+                row![
+                    text(entry.name()).size(14),
+                    text(folder).size(12).style(text::secondary)
+                ]
+                .spacing(8),
+            )
+            .padding([3, 6])
+            .width(Length::Fill)
+            .style(if index == quick_open.selected {
+                button::primary
+            } else {
+                button::text
+            })
+            .on_press(Message::QuickOpenFile(entry.path.clone()))
+            .into()
+        }))
+        .spacing(2)
+        .into()
+    };
+
+    let preview = text(quick_open.preview.as_deref().unwrap_or(""))
+        .font(Font::MONOSPACE)
+        .size(12);
+
+    let dialog = container(
+        column![
+            row![
+                input,
+                button(text("Close").size(13))
+                    .padding([8, 10])
+                    .style(button::secondary)
+                    .on_press(Message::CloseQuickOpen)
+            ]
+            .spacing(8),
+            row![
+                scrollable(results).width(Length::FillPortion(2)),
+                container(scrollable(preview))
+                    .padding(8)
+                    .width(Length::FillPortion(3))
+                    .height(Length::Fill)
+                    .style(container::bordered_box)
+            ]
+            .spacing(10)
+            .height(Length::Fill)
+        ]
+        .spacing(10),
+    )
+    .padding(12)
+    .width(760)
+    .height(440)
+    .style(dialog_style);
+
+    opaque(center(dialog).style(backdrop_style))
+}
+
+fn dialog_style(theme: &Theme) -> container::Style {
+    let palette = theme.extended_palette();
+    container::Style::default()
+        .background(palette.background.base.color)
+        .border(
+            iced::border::rounded(6)
+                .color(palette.background.strong.color)
+                .width(1),
+        )
+}
+
+/// Dims the workspace behind the finder.
+fn backdrop_style(_theme: &Theme) -> container::Style {
+    container::Style::default().background(Color {
+        a: 0.5,
+        ..Color::BLACK
+    })
+}
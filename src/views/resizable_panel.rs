@@ -1,47 +1,53 @@
 use iced::{
-    Background, Border, Color, Element,
+    Background, Border, Color, Element, Theme,
+    theme::palette::Extended,
     widget::{Container, container},
 };
 
 use crate::app::Message;
 
+/// Wraps `content` in a bordered container whose colors are picked from the active theme.
 pub fn with_border<'a>(
-    content: impl Into<Element<'a, Message>>, 
-    border_color: Color,
-    bg_color: Option<Color>
+    content: impl Into<Element<'a, Message>>,
+    border_color: fn(&Extended) -> Color,
+    bg_color: fn(&Extended) -> Option<Color>,
 ) -> Container<'a, Message> {
-    container(content).style(move |_| iced::widget::container::Style {
-        border: Border {
-            color: border_color,
-            width: 1.5,
-            radius: 3.0.into(),
-        },
-        background: bg_color.map(Background::Color),
-        ..Default::default()
-    })
-    .padding(5)
+    container(content)
+        .style(move |theme: &Theme| {
+            let palette = theme.extended_palette();
+            iced::widget::container::Style {
+                border: Border {
+                    color: border_color(palette),
+                    width: 1.5,
+                    radius: 3.0.into(),
+                },
+                background: bg_color(palette).map(Background::Color),
+                ..Default::default()
+            }
+        })
+        .padding(5)
 }
 
 pub fn main_panel<'a>(content: impl Into<Element<'a, Message>>) -> Container<'a, Message> {
     with_border(
         content,
-        Color::from_rgba(0.4, 0.4, 0.4, 0.8), // Darker gray with high opacity
-        Some(Color::from_rgba(0.98, 0.98, 0.98, 0.3)) // Very light background
+        |palette| palette.background.strong.color, // Neutral border
+        |palette| Some(palette.background.weak.color.scale_alpha(0.3)), // Faint background
     )
 }
 
 pub fn inspector_panel<'a>(content: impl Into<Element<'a, Message>>) -> Container<'a, Message> {
     with_border(
         content,
-        Color::from_rgba(0.3, 0.3, 0.5, 0.8), // Blue-gray with high opacity
-        Some(Color::from_rgba(0.95, 0.95, 1.0, 0.2)) // Very light blue background
+        |palette| palette.primary.weak.color, // Primary-tinted border
+        |palette| Some(palette.primary.weak.color.scale_alpha(0.2)), // Faint primary background
     )
 }
 
 pub fn file_tree_panel<'a>(content: impl Into<Element<'a, Message>>) -> Container<'a, Message> {
     with_border(
         content,
-        Color::from_rgba(0.3, 0.5, 0.3, 0.8), // Green-gray with high opacity
-        Some(Color::from_rgba(0.95, 1.0, 0.95, 0.2)) // Very light green background
+        |palette| palette.success.weak.color, // Success-tinted border
+        |palette| Some(palette.success.weak.color.scale_alpha(0.2)), // Faint success background
     )
 }
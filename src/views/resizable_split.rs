@@ -30,7 +30,7 @@ impl<'a, Message, Theme, Renderer> ResizableSplit<'a, Message, Theme, Renderer>
             first: first.into(),
             second: second.into(),
             is_horizontal: false, // Vertical split by default
-            ratio: ratio.max(0.1).min(0.9), // Constrain ratio
+            ratio: ratio.clamp(0.1, 0.9), // Constrain ratio
             on_resize: Box::new(on_resize),
             min_size: (50, 50), // Default minimum sizes
         }
@@ -47,18 +47,13 @@ impl<'a, Message, Theme, Renderer> ResizableSplit<'a, Message, Theme, Renderer>
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 enum DragState {
+    #[default]
     Idle,
     Dragging,
 }
 
-impl Default for DragState {
-    fn default() -> Self {
-        DragState::Idle
-    }
-}
-
 impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer> for ResizableSplit<'a, Message, Theme, Renderer>
 where
     Message: Clone,
@@ -120,12 +115,10 @@ where
             let second_layout = second_layout.move_to(Point::new(0.0, first_height));
             
             // Create the layout node
-            let node = Node::with_children(
+            Node::with_children(
                 Size::new(max_width, max_height),
                 vec![first_layout, second_layout],
-            );
-            
-            node
+            )
         } else {
             // Vertical split (first beside second)
             let max_width = max_size.width;
@@ -160,12 +153,10 @@ where
             let second_layout = second_layout.move_to(Point::new(first_width, 0.0));
             
             // Create the layout node
-            let node = Node::with_children(
+            Node::with_children(
                 Size::new(max_width, max_height),
                 vec![first_layout, second_layout],
-            );
-            
-            node
+            )
         }
     }
 
@@ -252,33 +243,33 @@ where
         let mut children = layout.children();
         
         // Draw the first child
-        if let Some(first_layout) = children.next() {
-            if let Some(first_state) = state.children.get(0) {
-                self.first.as_widget().draw(
-                    first_state,
-                    renderer,
-                    theme,
-                    style,
-                    first_layout,
-                    cursor,
-                    viewport,
-                );
-            }
+        if let Some(first_layout) = children.next()
+            && let Some(first_state) = state.children.first()
+        {
+            self.first.as_widget().draw(
+                first_state,
+                renderer,
+                theme,
+                style,
+                first_layout,
+                cursor,
+                viewport,
+            );
         }
         
         // Draw the second child
-        if let Some(second_layout) = children.next() {
-            if let Some(second_state) = state.children.get(1) {
-                self.second.as_widget().draw(
-                    second_state,
-                    renderer,
-                    theme,
-                    style,
-                    second_layout,
-                    cursor,
-                    viewport,
-                );
-            }
+        if let Some(second_layout) = children.next()
+            && let Some(second_state) = state.children.get(1)
+        {
+            self.second.as_widget().draw(
+                second_state,
+                renderer,
+                theme,
+                style,
+                second_layout,
+                cursor,
+                viewport,
+            );
         }
         
         // Draw the resizing handle
@@ -406,50 +397,46 @@ where
                 
                 match mouse_event {
                     mouse::Event::ButtonPressed(mouse::Button::Left) => {
-                        if let Some(cursor_position) = cursor.position() {
-                            if divider_bounds.contains(cursor_position) {
-                                if let iced::advanced::widget::tree::State::Some(state_box) = &mut state.state {
-                                    if let Some(state_ref) = state_box.downcast_mut::<DragState>() {
-                                        *state_ref = DragState::Dragging;
-                                    }
-                                }
-                                return event::Status::Captured;
+                        if let Some(cursor_position) = cursor.position()
+                            && divider_bounds.contains(cursor_position)
+                        {
+                            if let iced::advanced::widget::tree::State::Some(state_box) = &mut state.state
+                                && let Some(state_ref) = state_box.downcast_mut::<DragState>()
+                            {
+                                *state_ref = DragState::Dragging;
                             }
+                            return event::Status::Captured;
                         }
                     },
                     mouse::Event::CursorMoved { .. } => {
-                        if is_dragging {
-                            if let Some(cursor_position) = cursor.position() {
-                                let bounds = layout.bounds();
-                                
-                                // Calculate new ratio based on cursor position
-                                let new_ratio = if self.is_horizontal {
-                                    // For horizontal split (top/bottom), use y coordinate
-                                    ((cursor_position.y - bounds.y) / bounds.height)
-                                        .max(0.1)  // Ensure minimum size for first component
-                                        .min(0.9)  // Ensure minimum size for second component
-                                } else {
-                                    // For vertical split (left/right), use x coordinate
-                                    ((cursor_position.x - bounds.x) / bounds.width)
-                                        .max(0.1)  // Ensure minimum size for first component
-                                        .min(0.9)  // Ensure minimum size for second component
-                                };
-                                
-                                // Only publish if the ratio changed meaningfully
-                                shell.publish((self.on_resize)(new_ratio));
-                                return event::Status::Captured;
-                            }
+                        if is_dragging
+                            && let Some(cursor_position) = cursor.position()
+                        {
+                            let bounds = layout.bounds();
+                            
+                            // Calculate new ratio based on cursor position
+                            let new_ratio = if self.is_horizontal {
+                                // For horizontal split (top/bottom), use y coordinate
+                                ((cursor_position.y - bounds.y) / bounds.height)
+                                    .clamp(0.1, 0.9)  // Keep both components visible
+                            } else {
+                                // For vertical split (left/right), use x coordinate
+                                ((cursor_position.x - bounds.x) / bounds.width)
+                                    .clamp(0.1, 0.9)  // Keep both components visible
+                            };
+                            
+                            // Only publish if the ratio changed meaningfully
+                            shell.publish((self.on_resize)(new_ratio));
+                            return event::Status::Captured;
                         }
                     },
-                    mouse::Event::ButtonReleased(mouse::Button::Left) => {
-                        if is_dragging {
-                            if let iced::advanced::widget::tree::State::Some(state_box) = &mut state.state {
-                                if let Some(state_ref) = state_box.downcast_mut::<DragState>() {
-                                    *state_ref = DragState::Idle;
-                                }
-                            }
-                            return event::Status::Captured;
+                    mouse::Event::ButtonReleased(mouse::Button::Left) if is_dragging => {
+                        if let iced::advanced::widget::tree::State::Some(state_box) = &mut state.state
+                            && let Some(state_ref) = state_box.downcast_mut::<DragState>()
+                        {
+                            *state_ref = DragState::Idle;
                         }
+                        return event::Status::Captured;
                     },
                     _ => {}
                 }
@@ -532,8 +519,12 @@ where
         }
         
         // Now we can safely access the children
-        tree.children.get_mut(0).map(|child| self.first.as_widget().diff(child));
-        tree.children.get_mut(1).map(|child| self.second.as_widget().diff(child));
+        if let Some(child) = tree.children.get_mut(0) {
+            self.first.as_widget().diff(child);
+        }
+        if let Some(child) = tree.children.get_mut(1) {
+            self.second.as_widget().diff(child);
+        }
     }
     
     fn operate(
@@ -546,26 +537,26 @@ where
         operation.container(None, layout.bounds(), &mut |operation| {
             let mut children = layout.children();
             
-            if let Some(first_layout) = children.next() {
-                if let Some(first_child) = state.children.get_mut(0) {
-                    self.first.as_widget().operate(
-                        first_child,
-                        first_layout,
-                        renderer,
-                        operation,
-                    );
-                }
+            if let Some(first_layout) = children.next()
+                && let Some(first_child) = state.children.get_mut(0)
+            {
+                self.first.as_widget().operate(
+                    first_child,
+                    first_layout,
+                    renderer,
+                    operation,
+                );
             }
             
-            if let Some(second_layout) = children.next() {
-                if let Some(second_child) = state.children.get_mut(1) {
-                    self.second.as_widget().operate(
-                        second_child,
-                        second_layout,
-                        renderer,
-                        operation,
-                    );
-                }
+            if let Some(second_layout) = children.next()
+                && let Some(second_child) = state.children.get_mut(1)
+            {
+                self.second.as_widget().operate(
+                    second_child,
+                    second_layout,
+                    renderer,
+                    operation,
+                );
             }
         });
     }
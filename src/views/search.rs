@@ -0,0 +1,189 @@
+use std::sync::LazyLock;
+
+use iced::widget::{button, checkbox, column, pick_list, row, scrollable, text, text_input};
+use iced::{Element, Font, Length};
+
+use crate::Message;
+use crate::search::{LineMatch, Search, SearchMode};
+use crate::states::{SearchState, Workspace};
+
+/// The search field, focused when the panel opens.
+pub static SEARCH_INPUT_ID: LazyLock<text_input::Id> =
+    LazyLock::new(|| text_input::Id::new("project-search"));
+
+pub fn search_panel(workspace: &Workspace) -> Element<'_, Message> {
+    let state = &workspace.search;
+
+    let query = text_input(placeholder(state.mode), &state.query)
+        .id(SEARCH_INPUT_ID.clone())
+        .size(13)
+        .padding(6)
+        .on_input(Message::SearchQueryChanged)
+        .on_submit(Message::RunSearch);
+    let options = row![
+        pick_list(
+            SearchMode::ALL,
+            Some(state.mode),
+            Message::SearchModeSelected
+        )
+        .text_size(12),
+        checkbox("Match case", state.case_sensitive)
+            .text_size(12)
+            .size(14)
+            .on_toggle(Message::SearchCaseToggled),
+        small_button("Search", Some(Message::RunSearch))
+    ]
+    .spacing(8)
+    .align_y(iced::Alignment::Center);
+
+    let replace = row![
+        text_input(replacement_placeholder(state.mode), &state.replacement)
+            .size(13)
+            .padding(6)
+            .on_input(Message::SearchReplacementChanged),
+        small_button(
+            "Replace all…",
+            (state.search.is_some() && !state.results.is_empty())
+                .then_some(Message::PreviewReplace)
+        )
+    ]
+    .spacing(8)
+    .align_y(iced::Alignment::Center);
+
+    let mut controls = column![query, options, replace].spacing(6);
+    if !state.last_replace.is_empty() {
+        controls = controls.push(
+            row![
+                text(format!("Replaced in {} file(s)", state.last_replace.len()))
+                    .size(12)
+                    .width(Length::Fill),
+                small_button("Undo", Some(Message::UndoReplace))
+            ]
+            .align_y(iced::Alignment::Center),
+        );
+    }
+    if let Some(error) = &state.error {
+        controls = controls.push(text(error).size(12).style(text::danger));
+    } else if state.search.is_some() {
+        controls = controls.push(
+            text(format!(
+                "{} matching line(s) in {} file(s)",
+                state.match_count(),
+                state.results.len()
+            ))
+            .size(12)
+            .style(text::secondary),
+        );
+    }
+
+    let body = match (&state.search, state.previewing_replace) {
+        (Some(search), true) => replace_preview(state, search),
+        _ => results(state),
+    };
+
+    column![controls, scrollable(body).height(Length::Fill)]
+        .spacing(8)
+        .width(Length::Fill)
+        .into()
+}
+
+/// Matching lines grouped by file, each with the lines around it.
+fn results(state: &SearchState) -> Element<'_, Message> {
+    column(state.results.iter().map(|file| {
+        let lines = file.matches.iter().map(|line_match| {
+            button(match_context(line_match))
+                .padding([2, 4])
+                .width(Length::Fill)
+                .style(button::text)
+                .on_press(Message::SearchResultSelected(
+                    file.path.clone(),
+                    line_match.line,
+                ))
+                .into()
+        });
+        column![text(&file.key).size(13)]
+            .extend(lines)
+            .spacing(2)
+            .into()
+    }))
+    .spacing(10)
+    .width(Length::Fill)
+    .into()
+}
+
+fn match_context(line_match: &LineMatch) -> Element<'_, Message> {
+    let context = |line: &Option<String>| {
+        line.as_deref()
+            .map(|line| code(format!("      {line}")).style(text::secondary))
+    };
+    column![]
+        .push_maybe(context(&line_match.before))
+        .push(code(format!(
+            "{:>4}  {}",
+            line_match.line + 1,
+            line_match.text
+        )))
+        .push_maybe(context(&line_match.after))
+        .into()
+}
+
+/// Every matching line before and after the replacement, with the confirm buttons.
+fn replace_preview<'a>(state: &'a SearchState, search: &'a Search) -> Element<'a, Message> {
+    let files = state.results.iter().map(|file| {
+        let lines = file.matches.iter().map(|line_match| {
+            column![
+                code(format!("- {}", line_match.text)).style(text::danger),
+                code(format!(
+                    "+ {}",
+                    search.replace_line(&line_match.text, &state.replacement)
+                ))
+                .style(text::success)
+            ]
+            .into()
+        });
+        column![text(&file.key).size(13)]
+            .extend(lines)
+            .spacing(2)
+            .into()
+    });
+
+    column![
+        row![
+            small_button("Cancel", Some(Message::CancelReplace)),
+            small_button("Replace", Some(Message::ConfirmReplace))
+        ]
+        .spacing(8)
+    ]
+    .extend(files)
+    .spacing(10)
+    .width(Length::Fill)
+    .into()
+}
+
+fn placeholder(mode: SearchMode) -> &'static str {
+    match mode {
+        SearchMode::Text => "Search project files",
+        SearchMode::Regex => "Regular expression",
+        SearchMode::Json => "key or key=value",
+    }
+}
+
+fn replacement_placeholder(mode: SearchMode) -> &'static str {
+    match mode {
+        SearchMode::Text => "Replace with",
+        SearchMode::Regex => "Replace with ($1 for groups)",
+        SearchMode::Json => "New value as JSON, e.g. \"text\" or 42",
+    }
+}
+
+fn code<'a>(line: String) -> text::Text<'a> {
+    text(line).font(Font::MONOSPACE).size(12)
+}
+
+fn small_button(label: &str, message: Option<Message>) -> Element<'_, Message> {
+    button(text(label).size(12))
+        .padding([3, 8])
+        .style(button::secondary)
+        .on_press_maybe(message)
+        .into()
+}
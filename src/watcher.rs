@@ -0,0 +1,112 @@
+//! Filesystem watching that keeps open workspaces in sync with changes made outside the app.
+
+use std::path::{Path, PathBuf};
+
+use iced::{
+    Subscription,
+    futures::{SinkExt, StreamExt, channel::mpsc},
+    stream,
+};
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::{DispatchError, app::Message, states::WorkspaceId};
+
+/// Reports changes under `root` as [`Message::FilesChanged`] while the workspace stays open.
+///
+/// The watcher restarts whenever the workspace or its folder changes, since both are part of
+/// the subscription's identity. Reported paths are under `root` as given, even when it goes
+/// through a symlink (macOS puts temporary folders under `/var`, a link to `/private/var`) and
+/// the platform reports the resolved path.
+pub fn watch_workspace(id: WorkspaceId, root: PathBuf) -> Subscription<Message> {
+    Subscription::run_with_id(
+        (id, root.clone()),
+        stream::channel(64, move |mut output| async move {
+            let watch_error = |error: notify::Error| DispatchError::Io {
+                context: format!("watching {} for changes", root.display()),
+                message: error.to_string(),
+            };
+
+            let watched = root.canonicalize().unwrap_or_else(|_| root.clone());
+            let (sender, mut events) = mpsc::unbounded();
+            let watcher = notify::recommended_watcher(move |event| {
+                let _ = sender.unbounded_send(event);
+            })
+            .and_then(|mut watcher| {
+                watcher.watch(&watched, RecursiveMode::Recursive)?;
+                Ok(watcher)
+            });
+            // Dropping the watcher stops it, so it lives as long as this stream
+            let _watcher = match watcher {
+                Ok(watcher) => watcher,
+                Err(error) => {
+                    let _ = output
+                        .send(Message::FileWatchFailed(id, watch_error(error)))
+                        .await;
+                    return;
+                }
+            };
+
+            while let Some(event) = events.next().await {
+                // Coalesce bursts (checkouts, bulk copies) into a single refresh
+                let mut paths = Vec::new();
+                let mut next = Some(event);
+                while let Some(event) = next {
+                    match event {
+                        Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                            paths.extend(
+                                event
+                                    .paths
+                                    .into_iter()
+                                    .map(|path| under_root(path, &watched, &root)),
+                            );
+                        }
+                        Ok(_) => {}
+                        Err(error) => {
+                            let _ = output
+                                .send(Message::FileWatchFailed(id, watch_error(error)))
+                                .await;
+                        }
+                    }
+                    next = events.try_next().ok().flatten();
+                }
+
+                if !paths.is_empty() && output.send(Message::FilesChanged(id, paths)).await.is_err()
+                {
+                    break;
+                }
+            }
+        }),
+    )
+}
+
+/// Rewrites `path` from the canonical `watched` folder to the same place under `root`.
+fn under_root(path: PathBuf, watched: &Path, root: &Path) -> PathBuf {
+    match path.strip_prefix(watched) {
+        Ok(relative) => root.join(relative),
+        Err(_) => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_paths_are_reported_under_the_given_root() {
+        let root = Path::new("/var/folders/project");
+        let watched = Path::new("/private/var/folders/project");
+        assert_eq!(
+            under_root(watched.join("packets/login.json"), watched, root),
+            root.join("packets/login.json")
+        );
+        assert_eq!(under_root(watched.to_path_buf(), watched, root), root);
+        assert_eq!(
+            under_root(root.join("a.json"), root, root),
+            root.join("a.json")
+        );
+        assert_eq!(
+            under_root(PathBuf::from("/elsewhere/a.json"), watched, root),
+            PathBuf::from("/elsewhere/a.json")
+        );
+    }
+}